@@ -0,0 +1,216 @@
+//! An example of a single line text field driven by IME.
+//!
+//! The field is "rendered" to the window title, with the preedit shown in brackets at the cursor
+//! position, since drawing text is out of scope for winit.
+
+use simple_logger::SimpleLogger;
+use winit::{
+    dpi::{LogicalPosition, LogicalSize},
+    event::{ElementState, Event, Ime, KeyEvent, WindowEvent},
+    event_loop::EventLoop,
+    keyboard::{Key, NamedKey},
+    window::{ImePurpose, Window},
+};
+
+#[path = "util/fill.rs"]
+mod fill;
+
+/// The width of a character in the imaginary font, in logical pixels.
+const CHAR_WIDTH: f64 = 10.0;
+/// The height of the text line, in logical pixels.
+const LINE_HEIGHT: f64 = 20.0;
+/// The position of the text field inside the window, in logical pixels.
+const FIELD_ORIGIN: LogicalPosition<f64> = LogicalPosition::new(20.0, 20.0);
+
+#[derive(Default)]
+struct TextField {
+    /// The committed text.
+    text: String,
+    /// The cursor position in `text`, byte-wise indexed.
+    cursor: usize,
+    /// The text being composed and the cursor range inside of it.
+    preedit: Option<(String, Option<(usize, usize)>)>,
+}
+
+impl TextField {
+    fn insert(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    fn delete_before_cursor(&mut self) {
+        if let Some((idx, _)) = self.text[..self.cursor].char_indices().next_back() {
+            self.text.remove(idx);
+            self.cursor = idx;
+        }
+    }
+
+    /// Delete the bytes around the cursor, as far as they are on character boundaries.
+    fn delete_around_cursor(&mut self, before: usize, after: usize) {
+        let start = self.cursor.saturating_sub(before);
+        let end = self.cursor.saturating_add(after).min(self.text.len());
+        if self.text.is_char_boundary(start) && self.text.is_char_boundary(end) {
+            self.text.replace_range(start..end, "");
+            self.cursor = start;
+        }
+    }
+
+    fn move_cursor(&mut self, forward: bool) {
+        let next = if forward {
+            self.text[self.cursor..]
+                .chars()
+                .next()
+                .map(|c| self.cursor + c.len_utf8())
+        } else {
+            self.text[..self.cursor]
+                .char_indices()
+                .next_back()
+                .map(|(idx, _)| idx)
+        };
+
+        if let Some(next) = next {
+            self.cursor = next;
+        }
+    }
+
+    /// The area the IME candidate box should try not to obscure.
+    fn cursor_area(&self) -> (LogicalPosition<f64>, LogicalSize<f64>) {
+        let mut columns = self.text[..self.cursor].chars().count();
+        if let Some((preedit, Some((begin, _)))) = &self.preedit {
+            columns += preedit[..*begin].chars().count();
+        }
+
+        let position =
+            LogicalPosition::new(FIELD_ORIGIN.x + columns as f64 * CHAR_WIDTH, FIELD_ORIGIN.y);
+        (position, LogicalSize::new(CHAR_WIDTH, LINE_HEIGHT))
+    }
+
+    fn render(&self) -> String {
+        let (before, after) = self.text.split_at(self.cursor);
+        match &self.preedit {
+            Some((preedit, _)) => format!("{before}[{preedit}]{after}"),
+            None => format!("{before}|{after}"),
+        }
+    }
+}
+
+fn main() -> Result<(), impl std::error::Error> {
+    SimpleLogger::new().init().unwrap();
+
+    println!("Type to edit the text field shown in the window title.");
+    println!("Press 'F1' to toggle IME.");
+    println!("Press 'F2' to change the IME purpose.");
+    println!("Press 'Esc' to close the window.");
+
+    let event_loop = EventLoop::new().unwrap();
+
+    let mut window = None;
+    let mut field = TextField::default();
+    let mut ime_allowed = true;
+    let mut ime_purpose = ImePurpose::Normal;
+
+    event_loop.run(move |event, event_loop| match event {
+        Event::Resumed => {
+            let window_attributes = Window::default_attributes()
+                .with_title(field.render())
+                .with_inner_size(LogicalSize::new(640.0, 120.0));
+            let new_window = event_loop.create_window(window_attributes).unwrap();
+            new_window.set_ime_allowed(ime_allowed);
+            new_window.set_ime_purpose(ime_purpose);
+            window = Some(new_window);
+        }
+        Event::WindowEvent { event, .. } => {
+            let window = match window.as_ref() {
+                Some(window) => window,
+                None => return,
+            };
+
+            match event {
+                WindowEvent::CloseRequested => event_loop.exit(),
+                WindowEvent::Ime(event) => {
+                    println!("{event:?}");
+                    match event {
+                        // The candidate box is positioned below once IME is available.
+                        Ime::Enabled => (),
+                        Ime::Preedit(text, cursor_range) => {
+                            field.preedit = (!text.is_empty()).then_some((text, cursor_range));
+                        }
+                        Ime::Commit(text) => field.insert(&text),
+                        Ime::DeleteSurroundingText {
+                            before_bytes,
+                            after_bytes,
+                        } => field.delete_around_cursor(before_bytes, after_bytes),
+                        Ime::Update {
+                            delete_surrounding_text,
+                            commit,
+                            preedit: (text, cursor_range),
+                        } => {
+                            if let Some((before_bytes, after_bytes)) = delete_surrounding_text {
+                                field.delete_around_cursor(before_bytes, after_bytes);
+                            }
+                            if let Some(text) = commit {
+                                field.insert(&text);
+                            }
+                            field.preedit = (!text.is_empty()).then_some((text, cursor_range));
+                        }
+                        Ime::SurroundingTextUsed => (),
+                        Ime::Disabled => field.preedit = None,
+                    }
+
+                    let (position, size) = field.cursor_area();
+                    window.set_ime_cursor_area(position, size);
+                    window.set_ime_surrounding_text(field.text.clone(), field.cursor, field.cursor);
+                    window.set_title(&field.render());
+                }
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key,
+                            text,
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => {
+                    match logical_key.as_ref() {
+                        Key::Named(NamedKey::Escape) => event_loop.exit(),
+                        Key::Named(NamedKey::F1) => {
+                            ime_allowed = !ime_allowed;
+                            window.set_ime_allowed(ime_allowed);
+                            println!("IME allowed: {ime_allowed}");
+                        }
+                        Key::Named(NamedKey::F2) => {
+                            ime_purpose = match ime_purpose {
+                                ImePurpose::Normal => ImePurpose::Password,
+                                ImePurpose::Password => ImePurpose::Terminal,
+                                _ => ImePurpose::Normal,
+                            };
+                            window.set_ime_purpose(ime_purpose);
+                            println!("IME purpose: {ime_purpose:?}");
+                        }
+                        Key::Named(NamedKey::Backspace) => field.delete_before_cursor(),
+                        Key::Named(NamedKey::ArrowLeft) => field.move_cursor(false),
+                        Key::Named(NamedKey::ArrowRight) => field.move_cursor(true),
+                        // Text typed without IME, or while it's disabled.
+                        _ => match text {
+                            Some(text) if !text.chars().any(char::is_control) => {
+                                field.insert(&text)
+                            }
+                            _ => return,
+                        },
+                    }
+
+                    let (position, size) = field.cursor_area();
+                    window.set_ime_cursor_area(position, size);
+                    window.set_ime_surrounding_text(field.text.clone(), field.cursor, field.cursor);
+                    window.set_title(&field.render());
+                }
+                WindowEvent::RedrawRequested => {
+                    fill::fill_window(window);
+                }
+                _ => (),
+            }
+        }
+        _ => (),
+    })
+}
@@ -0,0 +1,78 @@
+//! An example of a splash window replaced by the main window.
+//!
+//! The main window is a separate window, since a window can't change what it is once created on
+//! some platforms, like Wayland. To not flicker, the splash is only closed once the main window
+//! presented its first frame.
+
+#[cfg(not(web_platform))]
+use std::time;
+#[cfg(web_platform)]
+use web_time as time;
+
+use simple_logger::SimpleLogger;
+use winit::{
+    dpi::LogicalSize,
+    event::{Event, StartCause, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+#[path = "util/fill.rs"]
+mod fill;
+
+/// How long the splash is shown, standing in for the loading of the application.
+const SPLASH_TIME: time::Duration = time::Duration::from_secs(2);
+
+fn main() -> Result<(), impl std::error::Error> {
+    SimpleLogger::new().init().unwrap();
+
+    let event_loop = EventLoop::new().unwrap();
+
+    let mut splash = None;
+    let mut main_window: Option<Window> = None;
+
+    event_loop.run(move |event, event_loop| match event {
+        Event::Resumed => {
+            let window_attributes = Window::default_attributes()
+                .with_title("Splash")
+                .with_decorations(false)
+                .with_resizable(false)
+                .with_inner_size(LogicalSize::new(400.0, 200.0));
+            splash = Some(event_loop.create_window(window_attributes).unwrap());
+            event_loop.set_control_flow(ControlFlow::WaitUntil(time::Instant::now() + SPLASH_TIME));
+        }
+        Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+            event_loop.set_control_flow(ControlFlow::Wait);
+
+            // Create the main window while the splash is still shown.
+            let window_attributes = Window::default_attributes().with_title("Main window");
+            let window = event_loop.create_window(window_attributes).unwrap();
+            window.request_redraw();
+            main_window = Some(window);
+        }
+        Event::WindowEvent { event, window_id } => match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::RedrawRequested => {
+                if let Some(window) = splash.as_ref().filter(|window| window.id() == window_id) {
+                    fill::fill_window(window);
+                }
+
+                if let Some(window) = main_window
+                    .as_ref()
+                    .filter(|window| window.id() == window_id)
+                {
+                    window.pre_present_notify();
+                    fill::fill_window(window);
+
+                    // The main window is on screen now, so the splash could go away.
+                    if let Some(splash) = splash.take() {
+                        fill::cleanup_window(&splash);
+                        println!("The splash was replaced by the main window.");
+                    }
+                }
+            }
+            _ => (),
+        },
+        _ => (),
+    })
+}
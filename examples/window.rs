@@ -272,6 +272,9 @@ impl Application {
                 window.modifiers = modifiers.state();
                 println!("Modifiers changed to {:?}", window.modifiers);
             }
+            WindowEvent::KeyboardLayoutChanged(layout) => {
+                println!("Keyboard layout changed to {layout:?}");
+            }
             WindowEvent::MouseWheel { delta, .. } => match delta {
                 MouseScrollDelta::LineDelta(x, y) => {
                     println!("Mouse wheel Line Delta: ({x},{y})");
@@ -335,6 +338,27 @@ impl Application {
                 Ime::Commit(text) => {
                     println!("Commited: {}", text);
                 }
+                Ime::DeleteSurroundingText {
+                    before_bytes,
+                    after_bytes,
+                } => {
+                    println!(
+                        "Delete {before_bytes} bytes before and {after_bytes} after the cursor"
+                    );
+                }
+                Ime::Update {
+                    delete_surrounding_text,
+                    commit,
+                    preedit,
+                } => {
+                    println!(
+                        "Update with deletion {delete_surrounding_text:?}, commit {commit:?} and \
+                         preedit {preedit:?}"
+                    );
+                }
+                Ime::SurroundingTextUsed => {
+                    println!("The surrounding text was used for Window={window_id:?}");
+                }
                 Ime::Disabled => println!("IME disabled for Window={window_id:?}"),
             },
             WindowEvent::PinchGesture { delta, .. } => {
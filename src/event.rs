@@ -220,6 +220,12 @@ pub enum Event<T: 'static> {
     ///
     /// This is not an ideal event to drive application rendering from and instead applications
     /// should render in response to [`WindowEvent::RedrawRequested`] events.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Sent exactly once per event loop iteration, after all the pending events
+    ///   including [`WindowEvent::RedrawRequested`]. Redraws requested from this event are
+    ///   delivered in the next iteration.
     AboutToWait,
 
     /// Emitted when the event loop is being shut down.
@@ -384,6 +390,14 @@ pub enum WindowEvent {
     /// The keyboard modifiers have changed.
     ModifiersChanged(Modifiers),
 
+    /// The active keyboard layout has changed, like when switching between the configured
+    /// layouts.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Only available on X11 and Wayland.**
+    KeyboardLayoutChanged(KeyboardLayout),
+
     /// An event from an input method.
     ///
     /// **Note:** You have to explicitly enable this event using [`Window::set_ime_allowed`].
@@ -434,10 +448,20 @@ pub enum WindowEvent {
     CursorLeft { device_id: DeviceId },
 
     /// A mouse wheel movement or touchpad scroll occurred.
+    ///
+    /// A scroll of the [`ScrollSource::Finger`] ends with [`TouchPhase::Ended`] once the fingers
+    /// are lifted, which is where the kinetic scrolling starts. When the system does the kinetic
+    /// scrolling itself, it follows as the [`ScrollSource::Continuous`] scroll.
     MouseWheel {
         device_id: DeviceId,
         delta: MouseScrollDelta,
         phase: TouchPhase,
+        /// What the scroll comes from, `None` when it's unknown.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Always `None`.
+        source: Option<ScrollSource>,
     },
 
     /// An mouse button press has been received.
@@ -555,7 +579,11 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / X11 / Wayland / Orbital:** Unsupported.
+    /// - **Wayland:** Uses the `color-scheme` of the XDG settings portal, and is only sent when
+    ///   the theme wasn't set with [`Window::set_theme`].
+    /// - **iOS / Android / X11 / Orbital:** Unsupported.
+    ///
+    /// [`Window::set_theme`]: crate::window::Window::set_theme
     ThemeChanged(Theme),
 
     /// The window has been occluded (completely hidden from view).
@@ -579,7 +607,10 @@ pub enum WindowEvent {
     /// ### Others
     ///
     /// - **Web:** Doesn't take into account CSS [`border`], [`padding`], or [`transform`].
-    /// - **Android / Wayland / Windows / Orbital:** Unsupported.
+    /// - **Wayland:** Sent when the compositor suspends the window and when it resumes it, which
+    ///   requires `xdg_wm_base` version 6. [`WindowEvent::RedrawRequested`] isn't sent while the
+    ///   window is suspended, the redraws requested in the meantime are delivered on resume.
+    /// - **Android / Windows / Orbital:** Unsupported.
     ///
     /// [`border`]: https://developer.mozilla.org/en-US/docs/Web/CSS/border
     /// [`padding`]: https://developer.mozilla.org/en-US/docs/Web/CSS/padding
@@ -792,6 +823,16 @@ pub struct KeyEvent {
     pub(crate) platform_specific: platform_impl::KeyEventExtra,
 }
 
+/// Describes the keyboard layout which became active.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyboardLayout {
+    /// The index of the layout, in the order the layouts are configured in.
+    pub index: u32,
+
+    /// The human readable name of the layout, like `English (US)`, when it's known.
+    pub name: Option<String>,
+}
+
 /// Describes keyboard modifiers event.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Modifiers {
@@ -903,6 +944,13 @@ impl From<ModifiersState> for Modifiers {
 /// Ime::Preedit("", None) // Synthetic event generated by winit to clear preedit.
 /// Ime::Commit("啊不")
 /// ```
+///
+/// The events for a window always follow the
+/// `Enabled -> (Preedit | DeleteSurroundingText | Commit | Update | SurroundingTextUsed)* -> Disabled`
+/// order, so [`Preedit`](Self::Preedit), [`DeleteSurroundingText`](Self::DeleteSurroundingText),
+/// [`Commit`](Self::Commit), [`Update`](Self::Update) and
+/// [`SurroundingTextUsed`](Self::SurroundingTextUsed) are only sent between
+/// [`Enabled`](Self::Enabled) and [`Disabled`](Self::Disabled).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Ime {
@@ -927,12 +975,76 @@ pub enum Ime {
     /// Right before this event winit will send empty [`Self::Preedit`] event.
     Commit(String),
 
+    /// Notifies when the text around the cursor should be deleted.
+    ///
+    /// The lengths are in UTF-8 bytes, before the start and after the end of the cursor or the
+    /// selection. The preedit is cleared before this event, and when text is also inserted, the
+    /// [`Commit`](Self::Commit) comes right after it.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Only sent when the text input protocol asks for it, usually by
+    ///   autocorrection or reconversion. When the text was set with
+    ///   [`Window::set_ime_surrounding_text`], the lengths never go past it and never cut a
+    ///   character.
+    /// - **Android / iOS / macOS / Orbital / Web / Windows / X11:** Unsupported.
+    ///
+    /// [`Window::set_ime_surrounding_text`]: crate::window::Window::set_ime_surrounding_text
+    DeleteSurroundingText {
+        before_bytes: usize,
+        after_bytes: usize,
+    },
+
+    /// Notifies of all the changes of one IME update at once, so they can be applied together,
+    /// like in a single undo step.
+    ///
+    /// The changes are applied in the order of the separate events: the preedit is cleared, the
+    /// text is deleted around the cursor, the commit is inserted at the cursor, and the preedit is
+    /// set.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Only sent, instead of the separate events, when enabled with
+    ///   `WindowExtWayland::set_ime_batched_updates`.
+    /// - **Android / iOS / macOS / Orbital / Web / Windows / X11:** Unsupported.
+    Update {
+        /// The bytes to delete before and after the cursor or the selection, like in
+        /// [`DeleteSurroundingText`](Self::DeleteSurroundingText).
+        delete_surrounding_text: Option<(usize, usize)>,
+
+        /// The text to insert, like in [`Commit`](Self::Commit).
+        commit: Option<String>,
+
+        /// The preedit after the update, like in [`Preedit`](Self::Preedit), which is empty when
+        /// there's none.
+        preedit: (String, Option<(usize, usize)>),
+    },
+
+    /// Notifies that the IME likely used the text set with [`Window::set_ime_surrounding_text`],
+    /// for example to reconvert the selection, right after the edits it made.
+    ///
+    /// This is a heuristic, since the IME doesn't tell what it took into account: it's sent when
+    /// the IME deletes the surrounding text, or commits text while the surrounding text has a
+    /// selection, which the commit replaces.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Android / iOS / macOS / Orbital / Web / Windows / X11:** Unsupported.
+    ///
+    /// [`Window::set_ime_surrounding_text`]: crate::window::Window::set_ime_surrounding_text
+    SurroundingTextUsed,
+
     /// Notifies when the IME was disabled.
     ///
     /// After receiving this event you won't get any more [`Preedit`](Self::Preedit) or
     /// [`Commit`](Self::Commit) events until the next [`Enabled`](Self::Enabled) event. You should
     /// also stop issuing IME related requests like [`Window::set_ime_cursor_area`] and clear pending
     /// preedit text.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** When the window loses the focus, this is sent a moment later, so the focus
+    ///   coming back right away doesn't disable and enable IME again.
     Disabled,
 }
 
@@ -1112,6 +1224,25 @@ pub enum MouseScrollDelta {
     PixelDelta(PhysicalPosition<f64>),
 }
 
+/// Describes what a scroll comes from.
+#[non_exhaustive]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScrollSource {
+    /// A scroll wheel, in steps.
+    Wheel,
+
+    /// The fingers on a touchpad or a touchscreen.
+    Finger,
+
+    /// A continuous motion without a defined end, like the kinetic scroll after lifting the
+    /// fingers, or a scroll from a trackball or a joystick.
+    Continuous,
+
+    /// The sideways tilt of a scroll wheel.
+    WheelTilt,
+}
+
 /// Handle to synchronously change the size of the window from the
 /// [`WindowEvent`].
 #[derive(Debug, Clone)]
@@ -1192,12 +1323,17 @@ mod tests {
                     position: (0, 0).into(),
                 });
                 with_window_event(ModifiersChanged(event::Modifiers::default()));
+                with_window_event(KeyboardLayoutChanged(event::KeyboardLayout {
+                    index: 0,
+                    name: None,
+                }));
                 with_window_event(CursorEntered { device_id: did });
                 with_window_event(CursorLeft { device_id: did });
                 with_window_event(MouseWheel {
                     device_id: did,
                     delta: event::MouseScrollDelta::LineDelta(0.0, 0.0),
                     phase: event::TouchPhase::Started,
+                    source: None,
                 });
                 with_window_event(MouseInput {
                     device_id: did,
@@ -7,6 +7,10 @@
 //! Such token is usually delivered via the environment variable and
 //! could be read from it with the [`EventLoopExtStartupNotify::read_token_from_env`].
 //!
+//! On Wayland, the token from the environment is used by the first created window
+//! automatically, unless it was given one with
+//! [`WindowAttributesExtStartupNotify::with_activation_token`].
+//!
 //! Such token must also be reset after reading it from your environment with
 //! [`reset_activation_token_env`] otherwise child processes could inherit it.
 //!
@@ -68,6 +72,12 @@ impl EventLoopExtStartupNotify for ActiveEventLoop {
     }
 }
 
+/// Read the token delivered to the application through the Wayland variable.
+#[cfg(wayland_platform)]
+pub(crate) fn wayland_token_from_env() -> Option<ActivationToken> {
+    env::var(WAYLAND_VAR).ok().map(ActivationToken::_new)
+}
+
 impl WindowExtStartupNotify for Window {
     fn request_activation_token(&self) -> Result<AsyncRequestSerial, NotSupportedError> {
         self.window.request_activation_token()
@@ -2,6 +2,15 @@
 //!
 //! **Note:** Windows don't appear on Wayland until you draw/present to them.
 //!
+//! Creating a window waits up to a second for the compositor to configure it. If it's slower
+//! than that, the window is created with the requested inner size, or 800x600 logical pixels
+//! when none was given, and [`WindowEvent::Resized`] is sent once the configure arrives.
+//! [`WindowEvent::RedrawRequested`] is held back until then, since the window can't be
+//! presented to before it's configured.
+//!
+//! [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+//! [`WindowEvent::RedrawRequested`]: crate::event::WindowEvent::RedrawRequested
+//!
 //! By default, Winit loads system libraries using `dlopen`. This can be
 //! disabled by disabling the `"wayland-dlopen"` cargo feature.
 //!
@@ -13,8 +22,36 @@
 //! * `wayland-csd-adwaita` (default).
 //! * `wayland-csd-adwaita-crossfont`.
 //! * `wayland-csd-adwaita-notitle`.
+//!
+//! ## System theme
+//!
+//! With the `wayland-portal` feature, Winit reads the system color scheme from the XDG settings
+//! portal over the session bus, which it connects to once the first window is created.
+//!
+//! ## Window state commits
+//!
+//! Winit doesn't commit the window surface on its own, except once when creating the window.
+//! The window state the protocol double-buffers, like the size limits, the blur, or the
+//! client-side decorations, is thus applied atomically on the next commit, which happens when a
+//! new buffer is presented. Making several changes between two frames doesn't need any
+//! batching and the compositor won't render the intermediate states.
+//!
+//! ## Splash screens
+//!
+//! What a surface is used for can't be changed once it's set, so a splash window can't be turned
+//! into the main window, and the latter is a new window with its own [`WindowId`]. Create the main
+//! window while the splash is shown, and drop the splash once the main window presented its first
+//! frame, so there's always a window on screen. See the `splash` example.
+//!
+//! [`WindowId`]: crate::window::WindowId
+use std::ffi::c_void;
+use std::ptr::NonNull;
+use std::time::Duration;
+
 use crate::{
-    event_loop::{ActiveEventLoop, EventLoopBuilder},
+    dpi::{Position, Size},
+    error::ExternalError,
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopBuilder},
     monitor::MonitorHandle,
     window::{Window, WindowAttributes},
 };
@@ -25,6 +62,43 @@ pub use crate::window::Theme;
 pub trait ActiveEventLoopExtWayland {
     /// True if the [`ActiveEventLoop`] uses Wayland.
     fn is_wayland(&self) -> bool;
+
+    /// The `wl_seat` objects currently bound by the event loop, as `*mut wl_proxy`.
+    ///
+    /// This allows binding seat-scoped protocols winit doesn't implement. The returned list is
+    /// empty when not using Wayland.
+    ///
+    /// The pointers are only valid until the seat is removed by the compositor or the event loop
+    /// is dropped, so don't keep them around; query them again instead. The seats are owned by
+    /// winit and must not be destroyed.
+    fn wl_seats(&self) -> Vec<NonNull<c_void>>;
+
+    /// The optional protocols supported by the compositor, to decide on a fallback before
+    /// enabling a feature which relies on them.
+    ///
+    /// None are reported when not using Wayland.
+    fn capabilities(&self) -> Capabilities;
+
+    /// Send the pending requests to the compositor right away.
+    ///
+    /// Winit flushes the connection every time the event loop is about to wait for new events,
+    /// so the requests made while handling the events are already sent in a single batch, and
+    /// there's no need to call this in the usual case. The requests are also flushed when the
+    /// buffer of the connection fills up.
+    ///
+    /// It's useful when something else is waiting on the compositor before winit gets to wait,
+    /// like a renderer blocking on a frame from another thread, in which case the requests
+    /// wouldn't reach the compositor and the wait would never end.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Unsupported.
+    fn flush(&self) -> Result<(), ExternalError>;
+
+    /// The compositor the event loop is connected to, to include in bug reports.
+    ///
+    /// `None` is returned when it can't be told, or when not using Wayland.
+    fn compositor_info(&self) -> Option<CompositorInfo>;
 }
 
 impl ActiveEventLoopExtWayland for ActiveEventLoop {
@@ -32,6 +106,124 @@ impl ActiveEventLoopExtWayland for ActiveEventLoop {
     fn is_wayland(&self) -> bool {
         self.p.is_wayland()
     }
+
+    #[inline]
+    fn wl_seats(&self) -> Vec<NonNull<c_void>> {
+        self.p.wl_seats()
+    }
+
+    #[inline]
+    fn capabilities(&self) -> Capabilities {
+        self.p.capabilities()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), ExternalError> {
+        self.p.flush()
+    }
+
+    #[inline]
+    fn compositor_info(&self) -> Option<CompositorInfo> {
+        self.p.compositor_info()
+    }
+}
+
+/// Additional methods on [`EventLoop`] that are specific to Wayland.
+pub trait EventLoopExtWayland {
+    /// The `wl_seat` objects currently bound by the event loop, as `*mut wl_proxy`.
+    ///
+    /// This allows binding seat-scoped protocols winit doesn't implement before the event loop
+    /// is run. The returned list is empty when not using Wayland.
+    ///
+    /// The pointers are only valid until the seat is removed by the compositor or the event loop
+    /// is dropped, so don't keep them around; query them again instead. The seats are owned by
+    /// winit and must not be destroyed.
+    fn wl_seats(&self) -> Vec<NonNull<c_void>>;
+
+    /// Send the pending requests to the compositor right away.
+    ///
+    /// When the event loop is driven with [`pump_events`], the requests made during the last
+    /// iteration are only sent when the event loop is pumped again. Call this before blocking
+    /// on something else, like an external event loop, for them to reach the compositor.
+    ///
+    /// Requests which aren't flushed never reach the compositor, so anything waiting on its
+    /// reply, like a frame callback, waits forever.
+    ///
+    /// The automatic flush can't be disabled. The requests made while handling the events are
+    /// already batched until the event loop is about to wait, and the Wayland event source
+    /// flushes on its own before sleeping, so disabling it wouldn't batch anything more, and
+    /// would only risk the requests never being sent.
+    ///
+    /// See [`ActiveEventLoopExtWayland::flush`] for the details.
+    ///
+    /// [`pump_events`]: crate::platform::pump_events::EventLoopExtPumpEvents::pump_events
+    fn flush(&self) -> Result<(), ExternalError>;
+
+    /// The compositor the event loop is connected to.
+    ///
+    /// See [`ActiveEventLoopExtWayland::compositor_info`] for the details.
+    fn compositor_info(&self) -> Option<CompositorInfo>;
+}
+
+impl<T> EventLoopExtWayland for EventLoop<T> {
+    #[inline]
+    fn wl_seats(&self) -> Vec<NonNull<c_void>> {
+        self.event_loop.window_target().p.wl_seats()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), ExternalError> {
+        self.event_loop.window_target().p.flush()
+    }
+
+    #[inline]
+    fn compositor_info(&self) -> Option<CompositorInfo> {
+        self.event_loop.window_target().p.compositor_info()
+    }
+}
+
+/// The compositor an event loop is connected to.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompositorInfo {
+    /// The name of the compositor process, like `sway` or `kwin_wayland`.
+    ///
+    /// When the process can't be seen, like from a sandbox, it's the desktop from
+    /// `XDG_CURRENT_DESKTOP` instead, like `GNOME` or `KDE`.
+    pub name: String,
+}
+
+/// The optional protocols bound by the event loop.
+///
+/// They're bound once when the event loop is created, so the capabilities don't change over its
+/// lifetime.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Fractional scaling, so [`Window::scale_factor`] isn't rounded to an integer.
+    pub fractional_scale: bool,
+    /// Activation, for [`Window::focus_window`] and
+    /// [`WindowExtStartupNotify::request_activation_token`].
+    ///
+    /// [`WindowExtStartupNotify::request_activation_token`]: crate::platform::startup_notify::WindowExtStartupNotify::request_activation_token
+    pub activation: bool,
+    /// Pointer constraints, for [`Window::set_cursor_grab`].
+    pub pointer_constraints: bool,
+    /// Relative pointer motion, for [`DeviceEvent::MouseMotion`].
+    ///
+    /// [`DeviceEvent::MouseMotion`]: crate::event::DeviceEvent::MouseMotion
+    pub relative_pointer: bool,
+    /// Text input, for the IME.
+    pub text_input: bool,
+    /// Server-side decorations. Without them, winit draws the decorations itself.
+    pub server_side_decorations: bool,
+    /// Subsurfaces, which the decorations drawn by winit rely on.
+    pub client_side_decorations: bool,
+    /// Cursor shapes, which the compositor draws and animates itself. Without them, winit loads
+    /// the cursor theme.
+    pub cursor_shape: bool,
+    /// The KWin blur, for [`Window::set_blur`].
+    pub blur: bool,
 }
 
 /// Additional methods on [`EventLoopBuilder`] that are specific to Wayland.
@@ -44,6 +236,56 @@ pub trait EventLoopBuilderExtWayland {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// A callback to transform the text of every [`Ime::Commit`] before it's delivered.
+    ///
+    /// This could be used to normalize or filter the committed text in a single place,
+    /// instead of doing so in every handler. By default the text is delivered as is.
+    ///
+    /// The callback is executed on the event loop thread, right when the compositor finishes
+    /// the IME update and before the resulting events are queued, so it's called exactly once
+    /// per commit and in the order the commits were received. It must not block.
+    ///
+    /// [`Ime::Commit`]: crate::event::Ime::Commit
+    fn with_ime_commit_transform<F>(&mut self, transform: F) -> &mut Self
+    where
+        F: FnMut(String) -> String + 'static;
+
+    /// Use the given xkb keymap, in the text format, to translate the keys instead of the
+    /// compositor's one.
+    ///
+    /// The compositor still sends its keymap to every keyboard, and the given keymap replaces it
+    /// each time, resetting the keyboard state the same way a keymap change would. The key
+    /// codes, the modifiers and the active layout group reported by the compositor are then
+    /// interpreted with the given keymap, so it should use the `evdev` key codes and keep the
+    /// modifiers the compositor's keymap has. When the keymap fails to compile, a warning is
+    /// logged and the compositor's keymap is used.
+    fn with_xkb_keymap<S: Into<String>>(&mut self, keymap: S) -> &mut Self;
+
+    /// Load the compose table, which combines the dead keys and the compose sequences, for the
+    /// given locale, like `en_US.UTF-8`.
+    ///
+    /// By default, and when the table for the locale can't be loaded, the locale is taken from
+    /// the `LC_ALL`, `LC_CTYPE`, and `LANG` environment variables, the first one set being used,
+    /// falling back to `C`. In both cases, the compose file is still looked up by libxkbcommon
+    /// in the following order, the locale only deciding the last one:
+    ///
+    /// 1. The file in `XCOMPOSEFILE`.
+    /// 2. The user's `XCompose` file, in `XDG_CONFIG_HOME` or the home directory.
+    /// 3. The system compose file for the locale.
+    fn with_compose_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self;
+
+    /// Hold back the scale factor changes of the windows until the scale factor stays the same
+    /// for `delay`, sending a single [`ScaleFactorChanged`] with the settled value.
+    ///
+    /// Dragging a window across outputs with different scales could change the scale factor
+    /// many times in a row, so this avoids reallocating the buffers for each change. No event
+    /// is sent when the scale factor settles back to the one the window had. Until then, the
+    /// window keeps rendering at its previous scale factor. By default, every change is sent
+    /// right away.
+    ///
+    /// [`ScaleFactorChanged`]: crate::event::WindowEvent::ScaleFactorChanged
+    fn with_scale_factor_coalescing(&mut self, delay: Duration) -> &mut Self;
 }
 
 impl<T> EventLoopBuilderExtWayland for EventLoopBuilder<T> {
@@ -58,12 +300,180 @@ impl<T> EventLoopBuilderExtWayland for EventLoopBuilder<T> {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_ime_commit_transform<F>(&mut self, transform: F) -> &mut Self
+    where
+        F: FnMut(String) -> String + 'static,
+    {
+        self.platform_specific.ime_commit_transform = Some(Box::new(transform));
+        self
+    }
+
+    #[inline]
+    fn with_xkb_keymap<S: Into<String>>(&mut self, keymap: S) -> &mut Self {
+        self.platform_specific.xkb_keymap = Some(keymap.into());
+        self
+    }
+
+    #[inline]
+    fn with_compose_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self {
+        self.platform_specific.compose_locale = Some(locale.into());
+        self
+    }
+
+    #[inline]
+    fn with_scale_factor_coalescing(&mut self, delay: Duration) -> &mut Self {
+        self.platform_specific.scale_coalescing = Some(delay);
+        self
+    }
 }
 
 /// Additional methods on [`Window`] that are specific to Wayland.
-pub trait WindowExtWayland {}
+pub trait WindowExtWayland {
+    /// Whether to send an empty [`Ime::Preedit`] at the start of every IME update.
+    ///
+    /// By default, the preedit is cleared before every change to it, which is convenient when
+    /// rendering it, but could cause flicker for applications which keep the old preedit on
+    /// screen until the new one arrives. When disabled, a new preedit replaces the old one
+    /// directly, and the empty [`Ime::Preedit`] is only sent when the preedit goes away or right
+    /// before an [`Ime::Commit`]. Either way, nothing is cleared when there's no preedit.
+    ///
+    /// The default is `true`.
+    ///
+    /// [`Ime::Preedit`]: crate::event::Ime::Preedit
+    /// [`Ime::Commit`]: crate::event::Ime::Commit
+    fn set_ime_preedit_clear_on_done(&self, clear: bool);
+
+    /// Whether to send all the IME changes of an update in a single [`Ime::Update`], instead of
+    /// the separate [`Ime::Preedit`], [`Ime::DeleteSurroundingText`] and [`Ime::Commit`].
+    ///
+    /// The default is `false`.
+    ///
+    /// [`Ime::Update`]: crate::event::Ime::Update
+    /// [`Ime::Preedit`]: crate::event::Ime::Preedit
+    /// [`Ime::DeleteSurroundingText`]: crate::event::Ime::DeleteSurroundingText
+    /// [`Ime::Commit`]: crate::event::Ime::Commit
+    fn set_ime_batched_updates(&self, batched: bool);
+
+    /// Whether to send the [`KeyboardInput`] for the key presses while the IME composes, as in
+    /// while there's a preedit.
+    ///
+    /// The input method takes the keys composing the text, but whether it passes the other ones
+    /// through while composing is up to it. When disabled, these key presses and their repeats
+    /// are held back, for the applications which'd handle them as raw keys otherwise, like
+    /// editor commands. The key releases are always sent.
+    ///
+    /// The default is `true`.
+    ///
+    /// [`KeyboardInput`]: crate::event::WindowEvent::KeyboardInput
+    fn set_ime_key_presses_while_composing(&self, deliver: bool);
+
+    /// Whether to only send [`WindowEvent::Resized`] once the user stops resizing the window.
+    ///
+    /// While the window is resized by dragging its edges, the compositor changes its size on
+    /// every pointer motion, each one resulting in a [`WindowEvent::Resized`]. When enabled,
+    /// those are skipped, and a single [`WindowEvent::Resized`] with the final size is sent when
+    /// the resize ends. The window still follows the pointer, so [`Window::inner_size`] returns
+    /// the current size and [`WindowEvent::RedrawRequested`] keeps coming.
+    ///
+    /// The default is `false`.
+    ///
+    /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
+    /// [`WindowEvent::RedrawRequested`]: crate::event::WindowEvent::RedrawRequested
+    fn set_debounce_interactive_resize(&self, debounce: bool);
+
+    /// Set the region of the window which accepts input, in surface local coordinates.
+    ///
+    /// Input outside of the region passes through to the windows below, which is useful for
+    /// transparent overlays. `None` means the whole window, which is the default, while an
+    /// empty region makes the whole window click-through.
+    ///
+    /// Disabling hit testing with [`Window::set_cursor_hittest`] takes precedence over the
+    /// region.
+    fn set_input_region(&self, region: Option<Vec<(Position, Size)>>) -> Result<(), ExternalError>;
+
+    /// The integer scale of the outputs the window is on, the largest one when it spans several.
+    ///
+    /// This is the scale the compositor expects the buffers to be rendered at without fractional
+    /// scaling, in which case it's equal to [`Window::scale_factor`]. With fractional scaling,
+    /// [`Window::scale_factor`] is the one to use for the layout, and it's usually at most this
+    /// value, so rendering at this scale and letting the compositor downscale is another option.
+    ///
+    /// The value is queried each time, so it follows the window across outputs.
+    ///
+    /// On X11, this is [`Window::scale_factor`] rounded up.
+    fn integer_scale_factor(&self) -> i32;
+
+    /// Whether the window is under fractional scaling, as in the compositor scales it with
+    /// `wp_fractional_scale_v1` and [`Window::scale_factor`] isn't an integer.
+    ///
+    /// The buffers then don't map to whole output pixels at the integer scale, so the renderers
+    /// snapping to the pixel grid should use [`Window::scale_factor`]. The value follows the
+    /// scale factor changes.
+    ///
+    /// On X11, this is always `false`.
+    fn has_fractional_scale(&self) -> bool;
+
+    /// Who draws the decorations of the window, as last agreed on with the compositor.
+    ///
+    /// Before the compositor configured the window, and when it doesn't support server side
+    /// decorations, this is [`DecorationMode::Client`].
+    ///
+    /// On X11, this is always [`DecorationMode::Server`], since the window manager draws them.
+    fn decoration_mode(&self) -> DecorationMode;
+}
+
+impl WindowExtWayland for Window {
+    #[inline]
+    fn set_ime_preedit_clear_on_done(&self, clear: bool) {
+        self.window.set_ime_preedit_clear_on_done(clear)
+    }
+
+    #[inline]
+    fn set_ime_batched_updates(&self, batched: bool) {
+        self.window.set_ime_batched_updates(batched)
+    }
+
+    #[inline]
+    fn set_ime_key_presses_while_composing(&self, deliver: bool) {
+        self.window.set_ime_key_presses_while_composing(deliver)
+    }
+
+    #[inline]
+    fn set_debounce_interactive_resize(&self, debounce: bool) {
+        self.window.set_debounce_interactive_resize(debounce)
+    }
+
+    #[inline]
+    fn set_input_region(&self, region: Option<Vec<(Position, Size)>>) -> Result<(), ExternalError> {
+        self.window.set_input_region(region)
+    }
+
+    #[inline]
+    fn integer_scale_factor(&self) -> i32 {
+        self.window.integer_scale_factor()
+    }
 
-impl WindowExtWayland for Window {}
+    #[inline]
+    fn has_fractional_scale(&self) -> bool {
+        self.window.has_fractional_scale()
+    }
+
+    #[inline]
+    fn decoration_mode(&self) -> DecorationMode {
+        self.window.decoration_mode()
+    }
+}
+
+/// Who draws the decorations of a window, see [`WindowExtWayland::decoration_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationMode {
+    /// The decorations are drawn by winit, when they're enabled.
+    Client,
+    /// The decorations are drawn by the compositor.
+    Server,
+}
 
 /// Additional methods on [`WindowAttributes`] that are specific to Wayland.
 pub trait WindowAttributesExtWayland {
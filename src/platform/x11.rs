@@ -113,6 +113,19 @@ pub trait EventLoopBuilderExtX11 {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// Load the compose table, which combines the dead keys and the compose sequences, for the
+    /// given locale, like `en_US.UTF-8`.
+    ///
+    /// By default, and when the table for the locale can't be loaded, the locale is taken from
+    /// the `LC_ALL`, `LC_CTYPE`, and `LANG` environment variables, the first one set being used,
+    /// falling back to `C`. In both cases, the compose file is still looked up by libxkbcommon
+    /// in the following order, the locale only deciding the last one:
+    ///
+    /// 1. The file in `XCOMPOSEFILE`.
+    /// 2. The user's `XCompose` file, in `XDG_CONFIG_HOME` or the home directory.
+    /// 3. The system compose file for the locale.
+    fn with_compose_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self;
 }
 
 impl<T> EventLoopBuilderExtX11 for EventLoopBuilder<T> {
@@ -127,6 +140,12 @@ impl<T> EventLoopBuilderExtX11 for EventLoopBuilder<T> {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_compose_locale<S: Into<String>>(&mut self, locale: S) -> &mut Self {
+        self.platform_specific.compose_locale = Some(locale.into());
+        self
+    }
 }
 
 /// Additional methods on [`Window`] that are specific to X11.
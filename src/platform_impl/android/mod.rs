@@ -27,8 +27,8 @@ use crate::{
     event_loop::{self, ActiveEventLoop as RootAEL, ControlFlow, DeviceEvents},
     platform::pump_events::PumpStatus,
     window::{
-        self, CursorGrabMode, CustomCursor, CustomCursorSource, ImePurpose, ResizeDirection, Theme,
-        WindowButtons, WindowLevel,
+        self, CursorGrabMode, CustomCursor, CustomCursorSource, ImeContentHints, ImePurpose,
+        ResizeDirection, Theme, WindowButtons, WindowLevel,
     },
 };
 use crate::{error::EventLoopError, platform_impl::Fullscreen};
@@ -841,6 +841,12 @@ impl Window {
 
     pub fn pre_present_notify(&self) {}
 
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, error::NotSupportedError> {
         Err(error::NotSupportedError::new())
     }
@@ -925,16 +931,42 @@ impl Window {
         true
     }
 
+    pub fn drag_and_drop_supported(&self) -> bool {
+        false
+    }
+
     pub fn set_window_level(&self, _level: WindowLevel) {}
 
     pub fn set_window_icon(&self, _window_icon: Option<crate::icon::Icon>) {}
 
     pub fn set_ime_cursor_area(&self, _position: Position, _size: Size) {}
 
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        None
+    }
+
+    pub fn is_ime_supported(&self) -> bool {
+        false
+    }
+
     pub fn set_ime_allowed(&self, _allowed: bool) {}
 
     pub fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {}
+
+    pub fn ime_allowed(&self) -> bool {
+        false
+    }
+
+    pub fn ime_purpose(&self) -> ImePurpose {
+        ImePurpose::Normal
+    }
+
+    pub fn set_ime_bypass(&self, _bypass: bool) {}
+
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {}
+
     pub fn focus_window(&self) {}
 
     pub fn request_user_attention(&self, _request_type: Option<window::UserAttentionType>) {}
@@ -947,6 +979,10 @@ impl Window {
         ))
     }
 
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
     pub fn set_cursor_grab(&self, _: CursorGrabMode) -> Result<(), error::ExternalError> {
         Err(error::ExternalError::NotSupported(
             error::NotSupportedError::new(),
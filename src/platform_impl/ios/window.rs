@@ -23,8 +23,8 @@ use crate::{
     platform::ios::{ScreenEdge, StatusBarStyle, ValidOrientations},
     platform_impl::platform::{app_state, monitor, ActiveEventLoop, Fullscreen, MonitorHandle},
     window::{
-        CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, WindowAttributes,
-        WindowButtons, WindowId as RootWindowId, WindowLevel,
+        CursorGrabMode, ImeContentHints, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+        WindowAttributes, WindowButtons, WindowId as RootWindowId, WindowLevel,
     },
 };
 
@@ -151,6 +151,12 @@ impl Inner {
 
     pub fn pre_present_notify(&self) {}
 
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     pub fn inner_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
         let safe_area = self.safe_area_screen_space();
         let position = LogicalPosition {
@@ -259,6 +265,10 @@ impl Inner {
         Err(ExternalError::NotSupported(NotSupportedError::new()))
     }
 
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
     pub fn set_cursor_grab(&self, _: CursorGrabMode) -> Result<(), ExternalError> {
         Err(ExternalError::NotSupported(NotSupportedError::new()))
     }
@@ -358,6 +368,10 @@ impl Inner {
         true
     }
 
+    pub fn drag_and_drop_supported(&self) -> bool {
+        false
+    }
+
     pub fn set_window_level(&self, _level: WindowLevel) {
         warn!("`Window::set_window_level` is ignored on iOS")
     }
@@ -370,6 +384,15 @@ impl Inner {
         warn!("`Window::set_ime_cursor_area` is ignored on iOS")
     }
 
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        warn!("`Window::ime_cursor_area` is ignored on iOS");
+        None
+    }
+
+    pub fn is_ime_supported(&self) -> bool {
+        false
+    }
+
     pub fn set_ime_allowed(&self, _allowed: bool) {
         warn!("`Window::set_ime_allowed` is ignored on iOS")
     }
@@ -378,6 +401,28 @@ impl Inner {
         warn!("`Window::set_ime_allowed` is ignored on iOS")
     }
 
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {
+        warn!("`Window::set_ime_content_hints` is ignored on iOS")
+    }
+
+    pub fn ime_allowed(&self) -> bool {
+        warn!("`Window::ime_allowed` is ignored on iOS");
+        false
+    }
+
+    pub fn ime_purpose(&self) -> ImePurpose {
+        warn!("`Window::ime_purpose` is ignored on iOS");
+        ImePurpose::Normal
+    }
+
+    pub fn set_ime_bypass(&self, _bypass: bool) {
+        warn!("`Window::set_ime_bypass` is ignored on iOS")
+    }
+
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {
+        warn!("`Window::set_ime_surrounding_text` is ignored on iOS")
+    }
+
     pub fn focus_window(&self) {
         warn!("`Window::set_focus` is ignored on iOS")
     }
@@ -1,7 +1,7 @@
 //! XKB compose handling.
 
 use std::env;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::ops::Deref;
 use std::os::unix::ffi::OsStringExt;
 use std::ptr::NonNull;
@@ -20,7 +20,18 @@ pub struct XkbComposeTable {
 }
 
 impl XkbComposeTable {
-    pub fn new(context: &XkbContext) -> Option<Self> {
+    /// Load the compose table of the given `locale`, or of the one from the environment.
+    ///
+    /// `XCOMPOSEFILE` and the user's compose file still take precedence over the locale, the
+    /// same way they do for every application.
+    pub fn new(context: &XkbContext, locale: Option<&CStr>) -> Option<Self> {
+        if let Some(locale) = locale {
+            match Self::from_locale(context, locale) {
+                Some(table) => return Some(table),
+                None => log::warn!("failed to load the compose table for {locale:?}"),
+            }
+        }
+
         let locale = env::var_os("LC_ALL")
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
             .or_else(|| env::var_os("LC_CTYPE"))
@@ -29,7 +40,10 @@ impl XkbComposeTable {
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
             .unwrap_or_else(|| "C".into());
         let locale = CString::new(locale.into_vec()).unwrap();
+        Self::from_locale(context, &locale)
+    }
 
+    fn from_locale(context: &XkbContext, locale: &CStr) -> Option<Self> {
         let table = unsafe {
             (XKBCH.xkb_compose_table_new_from_locale)(
                 context.as_ptr(),
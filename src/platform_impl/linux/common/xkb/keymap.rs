@@ -1,6 +1,6 @@
 //! XKB keymap.
 
-use std::ffi::c_char;
+use std::ffi::{c_char, CStr};
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
 
@@ -917,6 +917,7 @@ pub fn keysym_location(keysym: u32) -> KeyLocation {
 pub struct XkbKeymap {
     keymap: NonNull<xkb_keymap>,
     _mods_indices: ModsIndices,
+    layout_names: Vec<String>,
     pub _core_keyboard_id: i32,
 }
 
@@ -924,17 +925,29 @@ impl XkbKeymap {
     #[cfg(wayland_platform)]
     pub fn from_fd(context: &XkbContext, fd: OwnedFd, size: usize) -> Option<Self> {
         let map = unsafe { MmapOptions::new().len(size).map_copy_read_only(&fd).ok()? };
+        unsafe { Self::from_text(context, map.as_ptr() as *const _) }
+    }
+
+    /// Compile the keymap from its text representation.
+    #[cfg(wayland_platform)]
+    pub fn from_string(context: &XkbContext, keymap: &CStr) -> Option<Self> {
+        unsafe { Self::from_text(context, keymap.as_ptr()) }
+    }
 
+    /// # Safety
+    ///
+    /// The `text` must point to a nul terminated string.
+    #[cfg(wayland_platform)]
+    unsafe fn from_text(context: &XkbContext, text: *const c_char) -> Option<Self> {
         let keymap = unsafe {
-            let keymap = (XKBH.xkb_keymap_new_from_string)(
+            (XKBH.xkb_keymap_new_from_string)(
                 (*context).as_ptr(),
-                map.as_ptr() as *const _,
+                text,
                 xkb::xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
                 xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
-            );
-            NonNull::new(keymap)?
+            )
         };
-
+        let keymap = NonNull::new(keymap)?;
         Some(Self::new_inner(keymap, 0))
     }
 
@@ -971,10 +984,40 @@ impl XkbKeymap {
         Self {
             keymap,
             _mods_indices: mods_indices,
+            layout_names: layout_names(keymap),
             _core_keyboard_id,
         }
     }
 
+    #[cfg(test)]
+    pub fn from_layouts(context: &XkbContext, layouts: &CStr) -> Option<Self> {
+        let names = xkb::xkb_rule_names {
+            rules: ptr::null(),
+            model: ptr::null(),
+            layout: layouts.as_ptr(),
+            variant: ptr::null(),
+            options: ptr::null(),
+        };
+
+        let keymap = unsafe {
+            (XKBH.xkb_keymap_new_from_names)(
+                (*context).as_ptr(),
+                &names,
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        let keymap = NonNull::new(keymap)?;
+        Some(Self::new_inner(keymap, 0))
+    }
+
+    /// The name of the layout at the given index.
+    pub fn layout_name(&self, layout: xkb_layout_index_t) -> Option<&str> {
+        self.layout_names
+            .get(layout as usize)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
     #[cfg(x11_platform)]
     pub fn mods_indices(&self) -> ModsIndices {
         self._mods_indices
@@ -1038,6 +1081,54 @@ pub struct ModsIndices {
     pub mod5: Option<xkb_mod_index_t>,
 }
 
+/// The names of the layouts, since `xkbcommon-dl` doesn't bind `xkb_keymap_layout_get_name`.
+fn layout_names(keymap: NonNull<xkb_keymap>) -> Vec<String> {
+    unsafe {
+        let text = (XKBH.xkb_keymap_get_as_string)(
+            keymap.as_ptr(),
+            xkb::xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
+        );
+        if text.is_null() {
+            return Vec::new();
+        }
+
+        let names = parse_layout_names(&CStr::from_ptr(text).to_string_lossy());
+        libc::free(text as *mut _);
+        names
+    }
+}
+
+/// Parse the layout names from the `name[Group1]="English (US)";` lines of the keymap.
+fn parse_layout_names(keymap: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in keymap.lines() {
+        let rest = match line.trim().strip_prefix("name[") {
+            Some(rest) => rest,
+            None => continue,
+        };
+
+        let (group, name) = match rest.split_once(']') {
+            Some(group) => group,
+            None => continue,
+        };
+
+        let group = group.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let index = match group.parse::<usize>() {
+            Ok(group) if group > 0 => group - 1,
+            _ => continue,
+        };
+
+        let name = name.trim_start_matches([' ', '=']).trim_end_matches(';');
+        let name = name.trim_matches('"');
+        if names.len() <= index {
+            names.resize(index + 1, String::new());
+        }
+        names[index] = name.to_owned();
+    }
+
+    names
+}
+
 fn mod_index_for_name(keymap: NonNull<xkb_keymap>, name: &[u8]) -> Option<xkb_mod_index_t> {
     unsafe {
         let mod_index =
@@ -1049,3 +1140,28 @@ fn mod_index_for_name(keymap: NonNull<xkb_keymap>, name: &[u8]) -> Option<xkb_mo
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_layout_names() {
+        let keymap = "xkb_keymap {\n\
+                      \txkb_types \"complete\" {\n\
+                      \t\tlevel_name[1]= \"Base\";\n\
+                      \t};\n\
+                      \txkb_symbols \"pc+us+ru:2\" {\n\
+                      \tname[Group1]=\"English (US)\";\n\
+                      \tname[Group2]=\"Russian\";\n\
+                      \t};\n\
+                      };\n";
+
+        assert_eq!(parse_layout_names(keymap), ["English (US)", "Russian"]);
+        assert_eq!(
+            parse_layout_names("name[Group2]=\"Russian\";"),
+            ["", "Russian"]
+        );
+        assert!(parse_layout_names("").is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use std::ffi::{CStr, CString};
 use std::ops::Deref;
 use std::os::raw::c_char;
 use std::ptr::{self, NonNull};
@@ -9,14 +10,14 @@ use smol_str::SmolStr;
 #[cfg(wayland_platform)]
 use std::os::unix::io::OwnedFd;
 use xkbcommon_dl::{
-    self as xkb, xkb_compose_status, xkb_context, xkb_context_flags, xkbcommon_compose_handle,
-    xkbcommon_handle, XkbCommon, XkbCommonCompose,
+    self as xkb, xkb_compose_status, xkb_context, xkb_context_flags, xkb_layout_index_t,
+    xkbcommon_compose_handle, xkbcommon_handle, XkbCommon, XkbCommonCompose,
 };
 #[cfg(x11_platform)]
 use {x11_dl::xlib_xcb::xcb_connection_t, xkbcommon_dl::x11::xkbcommon_x11_handle};
 
 use crate::event::ElementState;
-use crate::event::KeyEvent;
+use crate::event::{KeyEvent, KeyboardLayout};
 use crate::keyboard::{Key, KeyLocation};
 use crate::platform_impl::KeyEventExtra;
 
@@ -63,16 +64,20 @@ pub struct Context {
     _compose_table: Option<XkbComposeTable>,
     context: XkbContext,
     scratch_buffer: Vec<u8>,
+    /// The layout last reported to the user.
+    layout: xkb_layout_index_t,
 }
 
 impl Context {
-    pub fn new() -> Result<Self, Error> {
+    /// The compose table is loaded for the `compose_locale`, or for the locale from the
+    /// environment.
+    pub fn new(compose_locale: Option<&CStr>) -> Result<Self, Error> {
         if xkb::xkbcommon_option().is_none() {
             return Err(Error::XKBNotFound);
         }
 
         let context = XkbContext::new()?;
-        let mut compose_table = XkbComposeTable::new(&context);
+        let mut compose_table = XkbComposeTable::new(&context, compose_locale);
         let mut compose_state1 = compose_table.as_ref().and_then(|table| table.new_state());
         let mut compose_state2 = compose_table.as_ref().and_then(|table| table.new_state());
 
@@ -93,11 +98,15 @@ impl Context {
             _compose_table: compose_table,
             context,
             scratch_buffer: Vec::with_capacity(8),
+            layout: 0,
         })
     }
 
     #[cfg(feature = "x11")]
-    pub fn from_x11_xkb(xcb: *mut xcb_connection_t) -> Result<Self, Error> {
+    pub fn from_x11_xkb(
+        xcb: *mut xcb_connection_t,
+        compose_locale: Option<&CStr>,
+    ) -> Result<Self, Error> {
         let result = unsafe {
             (XKBXH.xkb_x11_setup_xkb_extension)(
                 xcb,
@@ -115,7 +124,7 @@ impl Context {
             return Err(Error::XKBNotFound);
         }
 
-        let mut this = Self::new()?;
+        let mut this = Self::new(compose_locale)?;
         this.core_keyboard_id = unsafe { (XKBXH.xkb_x11_get_core_keyboard_device_id)(xcb) };
         this.set_keymap_from_x11(xcb);
         Ok(this)
@@ -140,6 +149,26 @@ impl Context {
         self.keymap = keymap;
     }
 
+    /// Use the given keymap instead of the one provided by the compositor.
+    ///
+    /// On failure the current keymap is kept and `false` is returned.
+    #[cfg(wayland_platform)]
+    pub fn set_keymap_from_string(&mut self, keymap: &std::ffi::CStr) -> bool {
+        let keymap = match XkbKeymap::from_string(&self.context, keymap) {
+            Some(keymap) => keymap,
+            None => return false,
+        };
+
+        match XkbState::new_wayland(&keymap) {
+            Some(state) => {
+                self.state = Some(state);
+                self.keymap = Some(keymap);
+                true
+            }
+            None => false,
+        }
+    }
+
     #[cfg(x11_platform)]
     pub fn set_keymap_from_x11(&mut self, xcb: *mut xcb_connection_t) {
         let keymap = XkbKeymap::from_x11_keymap(&self.context, xcb, self.core_keyboard_id);
@@ -153,6 +182,33 @@ impl Context {
         self.keymap = keymap;
     }
 
+    #[cfg(test)]
+    fn set_keymap_from_layouts(&mut self, layouts: &std::ffi::CStr) {
+        let keymap = XkbKeymap::from_layouts(&self.context, layouts);
+        let state = keymap.as_ref().and_then(|keymap| {
+            let state = unsafe { (XKBH.xkb_state_new)(keymap.as_ptr()) };
+            NonNull::new(state).map(XkbState::new_inner)
+        });
+        self.state = state;
+        self.keymap = keymap;
+    }
+
+    /// The active keyboard layout, when it changed since the last time it was reported.
+    pub fn take_layout_change(&mut self) -> Option<KeyboardLayout> {
+        let index = self.state.as_ref()?.layout_index();
+        if index == self.layout {
+            return None;
+        }
+
+        self.layout = index;
+        let name = self
+            .keymap
+            .as_ref()
+            .and_then(|keymap| keymap.layout_name(index))
+            .map(str::to_owned);
+        Some(KeyboardLayout { index, name })
+    }
+
     /// Key builder context with the user provided xkb state.
     pub fn key_context(&mut self) -> Option<KeyContext<'_>> {
         let state = self.state.as_mut()?;
@@ -445,6 +501,13 @@ where
     byte_slice_to_smol_str(scratch_buffer)
 }
 
+/// The compose locale requested by the user, which is ignored when it can't be passed to xkb.
+pub fn compose_locale(locale: Option<String>) -> Option<CString> {
+    CString::new(locale?)
+        .map_err(|_| warn!("the compose locale contains a nul byte, ignoring it"))
+        .ok()
+}
+
 // NOTE: This is track_caller so we can have more informative line numbers when logging
 #[track_caller]
 fn byte_slice_to_smol_str(bytes: &[u8]) -> Option<SmolStr> {
@@ -458,3 +521,100 @@ fn byte_slice_to_smol_str(bytes: &[u8]) -> Option<SmolStr> {
         })
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_layout_group_switch() {
+        // The tests could run without `libxkbcommon`.
+        let mut context = match Context::new(None) {
+            Ok(context) => context,
+            Err(_) => return,
+        };
+
+        let layouts = std::ffi::CStr::from_bytes_with_nul(b"us,ru\0").unwrap();
+        context.set_keymap_from_layouts(layouts);
+        if context.state_mut().is_none() {
+            return;
+        }
+
+        assert_eq!(context.take_layout_change(), None);
+
+        // Lock the second group, like a layout switch does.
+        context
+            .state_mut()
+            .unwrap()
+            .update_modifiers(0, 0, 0, 0, 0, 1);
+        assert_eq!(
+            context.take_layout_change(),
+            Some(KeyboardLayout {
+                index: 1,
+                name: Some(String::from("Russian")),
+            })
+        );
+        assert_eq!(context.take_layout_change(), None);
+
+        context
+            .state_mut()
+            .unwrap()
+            .update_modifiers(0, 0, 0, 0, 0, 0);
+        assert_eq!(
+            context.take_layout_change().map(|layout| layout.index),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn compose_locale_without_nul() {
+        assert_eq!(compose_locale(None), None);
+        assert_eq!(
+            compose_locale(Some(String::from("de_DE.UTF-8"))).as_deref(),
+            Some(std::ffi::CStr::from_bytes_with_nul(b"de_DE.UTF-8\0").unwrap())
+        );
+        assert_eq!(compose_locale(Some(String::from("de\0DE"))), None);
+
+        // An unknown locale falls back to the one from the environment.
+        let _ = Context::new(compose_locale(Some(String::from("xx_XX"))).as_deref());
+    }
+
+    #[test]
+    #[cfg(wayland_platform)]
+    fn custom_keymap_replaces_the_current_one() {
+        let mut context = match Context::new(None) {
+            Ok(context) => context,
+            Err(_) => return,
+        };
+
+        let layouts = std::ffi::CStr::from_bytes_with_nul(b"us\0").unwrap();
+        context.set_keymap_from_layouts(layouts);
+        if context.state_mut().is_none() {
+            return;
+        }
+
+        let layout_name = |context: &mut Context| {
+            let keymap = context.keymap_mut().unwrap();
+            keymap.layout_name(0).map(String::from)
+        };
+        let us = layout_name(&mut context);
+
+        // A broken keymap leaves the current one in place.
+        let broken = std::ffi::CStr::from_bytes_with_nul(b"xkb_keymap {\0").unwrap();
+        assert!(!context.set_keymap_from_string(broken));
+        assert_eq!(layout_name(&mut context), us);
+
+        let keymap = std::ffi::CStr::from_bytes_with_nul(
+            b"xkb_keymap {
+                xkb_keycodes { include \"evdev\" };
+                xkb_types { include \"complete\" };
+                xkb_compat { include \"complete\" };
+                xkb_symbols { include \"pc+de\" };
+            };\0",
+        )
+        .unwrap();
+        assert!(context.set_keymap_from_string(keymap));
+        assert_eq!(layout_name(&mut context).as_deref(), Some("German"));
+        assert!(context.state_mut().is_some());
+    }
+}
@@ -37,7 +37,7 @@ impl XkbState {
         Some(Self::new_inner(state))
     }
 
-    fn new_inner(state: NonNull<xkb_state>) -> Self {
+    pub(super) fn new_inner(state: NonNull<xkb_state>) -> Self {
         let modifiers = ModifiersState::default();
         let mut this = Self { state, modifiers };
         this.reload_modifiers();
@@ -52,6 +52,16 @@ impl XkbState {
         unsafe { (XKBH.xkb_state_key_get_layout)(self.state.as_ptr(), key) }
     }
 
+    /// The index of the active layout.
+    pub fn layout_index(&self) -> xkb_layout_index_t {
+        unsafe {
+            (XKBH.xkb_state_serialize_layout)(
+                self.state.as_ptr(),
+                xkb_state_component::XKB_STATE_LAYOUT_EFFECTIVE,
+            )
+        }
+    }
+
     #[cfg(x11_platform)]
     pub fn depressed_modifiers(&mut self) -> xkb::xkb_mod_mask_t {
         unsafe {
@@ -29,8 +29,8 @@ use crate::{
     keyboard::Key,
     platform::pump_events::PumpStatus,
     window::{
-        ActivationToken, Cursor, CursorGrabMode, ImePurpose, ResizeDirection, Theme,
-        UserAttentionType, WindowAttributes, WindowButtons, WindowLevel,
+        ActivationToken, Cursor, CursorGrabMode, ImeContentHints, ImePurpose, ResizeDirection,
+        Theme, UserAttentionType, WindowAttributes, WindowButtons, WindowLevel,
     },
 };
 
@@ -53,10 +53,17 @@ pub(crate) enum Backend {
     Wayland,
 }
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Default)]
 pub(crate) struct PlatformSpecificEventLoopAttributes {
     pub(crate) forced_backend: Option<Backend>,
     pub(crate) any_thread: bool,
+    #[cfg(wayland_platform)]
+    pub(crate) ime_commit_transform: Option<Box<dyn FnMut(String) -> String + 'static>>,
+    #[cfg(wayland_platform)]
+    pub(crate) xkb_keymap: Option<String>,
+    pub(crate) compose_locale: Option<String>,
+    #[cfg(wayland_platform)]
+    pub(crate) scale_coalescing: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -459,6 +466,11 @@ impl Window {
         x11_or_wayland!(match self; Window(w) => w.set_cursor_position(position))
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        x11_or_wayland!(match self; Window(w) => w.cursor_position())
+    }
+
     #[inline]
     pub fn set_maximized(&self, maximized: bool) {
         x11_or_wayland!(match self; Window(w) => w.set_maximized(maximized))
@@ -499,6 +511,11 @@ impl Window {
         x11_or_wayland!(match self; Window(w) => w.is_decorated())
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        x11_or_wayland!(match self; Window(w) => w.drag_and_drop_supported())
+    }
+
     #[inline]
     pub fn set_window_level(&self, level: WindowLevel) {
         x11_or_wayland!(match self; Window(w) => w.set_window_level(level))
@@ -514,11 +531,104 @@ impl Window {
         x11_or_wayland!(match self; Window(w) => w.set_ime_cursor_area(position, size))
     }
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        x11_or_wayland!(match self; Window(w) => w.ime_cursor_area())
+    }
+
     #[inline]
     pub fn reset_dead_keys(&self) {
         common::xkb::reset_dead_keys()
     }
 
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn set_ime_preedit_clear_on_done(&self, clear: bool) {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => (),
+            Window::Wayland(ref window) => window.set_ime_preedit_clear_on_done(clear),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn set_ime_batched_updates(&self, batched: bool) {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => (),
+            Window::Wayland(ref window) => window.set_ime_batched_updates(batched),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn set_ime_key_presses_while_composing(&self, deliver: bool) {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => (),
+            Window::Wayland(ref window) => window.set_ime_key_presses_while_composing(deliver),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn set_debounce_interactive_resize(&self, debounce: bool) {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => (),
+            Window::Wayland(ref window) => window.set_debounce_interactive_resize(debounce),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn set_input_region(
+        &self,
+        region: Option<Vec<(Position, Size)>>,
+    ) -> Result<(), ExternalError> {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => Err(ExternalError::NotSupported(NotSupportedError::new())),
+            Window::Wayland(ref window) => window.set_input_region(region),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn integer_scale_factor(&self) -> i32 {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(ref window) => window.scale_factor().ceil() as i32,
+            Window::Wayland(ref window) => window.integer_scale_factor(),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn has_fractional_scale(&self) -> bool {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => false,
+            Window::Wayland(ref window) => window.has_fractional_scale(),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    #[inline]
+    pub(crate) fn decoration_mode(&self) -> crate::platform::wayland::DecorationMode {
+        match self {
+            #[cfg(x11_platform)]
+            Window::X(_) => crate::platform::wayland::DecorationMode::Server,
+            Window::Wayland(ref window) => window.decoration_mode(),
+        }
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        x11_or_wayland!(match self; Window(w) => w.is_ime_supported())
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, allowed: bool) {
         x11_or_wayland!(match self; Window(w) => w.set_ime_allowed(allowed))
@@ -529,6 +639,31 @@ impl Window {
         x11_or_wayland!(match self; Window(w) => w.set_ime_purpose(purpose))
     }
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, hints: ImeContentHints) {
+        x11_or_wayland!(match self; Window(w) => w.set_ime_content_hints(hints))
+    }
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        x11_or_wayland!(match self; Window(w) => w.ime_allowed())
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        x11_or_wayland!(match self; Window(w) => w.ime_purpose())
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, bypass: bool) {
+        x11_or_wayland!(match self; Window(w) => w.set_ime_bypass(bypass))
+    }
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, text: String, cursor: usize, anchor: usize) {
+        x11_or_wayland!(match self; Window(w) => w.set_ime_surrounding_text(text, cursor, anchor))
+    }
+
     #[inline]
     pub fn focus_window(&self) {
         x11_or_wayland!(match self; Window(w) => w.focus_window())
@@ -547,6 +682,16 @@ impl Window {
         x11_or_wayland!(match self; Window(w) => w.pre_present_notify())
     }
 
+    #[inline]
+    pub fn set_max_frame_rate(&self, rate: Option<u32>) {
+        x11_or_wayland!(match self; Window(w) => w.set_max_frame_rate(rate))
+    }
+
+    #[inline]
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        x11_or_wayland!(match self; Window(w) => w.frame_stats())
+    }
+
     #[inline]
     pub fn current_monitor(&self) -> Option<MonitorHandle> {
         Some(x11_or_wayland!(match self; Window(w) => w.current_monitor()?; as MonitorHandle))
@@ -719,7 +864,7 @@ impl<T: 'static> Clone for EventLoopProxy<T> {
 
 impl<T: 'static> EventLoop<T> {
     pub(crate) fn new(
-        attributes: &PlatformSpecificEventLoopAttributes,
+        attributes: &mut PlatformSpecificEventLoopAttributes,
     ) -> Result<Self, EventLoopError> {
         if !attributes.any_thread && !is_main_thread() {
             panic!(
@@ -768,25 +913,30 @@ impl<T: 'static> EventLoop<T> {
         // Create the display based on the backend.
         match backend {
             #[cfg(wayland_platform)]
-            Backend::Wayland => EventLoop::new_wayland_any_thread().map_err(Into::into),
+            Backend::Wayland => EventLoop::new_wayland_any_thread(attributes).map_err(Into::into),
             #[cfg(x11_platform)]
-            Backend::X => EventLoop::new_x11_any_thread().map_err(Into::into),
+            Backend::X => EventLoop::new_x11_any_thread(attributes).map_err(Into::into),
         }
     }
 
     #[cfg(wayland_platform)]
-    fn new_wayland_any_thread() -> Result<EventLoop<T>, EventLoopError> {
-        wayland::EventLoop::new().map(|evlp| EventLoop::Wayland(Box::new(evlp)))
+    fn new_wayland_any_thread(
+        attributes: &mut PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop<T>, EventLoopError> {
+        wayland::EventLoop::new(attributes).map(|evlp| EventLoop::Wayland(Box::new(evlp)))
     }
 
     #[cfg(x11_platform)]
-    fn new_x11_any_thread() -> Result<EventLoop<T>, EventLoopError> {
+    fn new_x11_any_thread(
+        attributes: &mut PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop<T>, EventLoopError> {
         let xconn = match X11_BACKEND.lock().unwrap().as_ref() {
             Ok(xconn) => xconn.clone(),
             Err(_) => return Err(EventLoopError::NotSupported(NotSupportedError::new())),
         };
 
-        Ok(EventLoop::X(x11::EventLoop::new(xconn)))
+        let compose_locale = common::xkb::compose_locale(attributes.compose_locale.take());
+        Ok(EventLoop::X(x11::EventLoop::new(xconn, compose_locale)))
     }
 
     pub fn create_proxy(&self) -> EventLoopProxy<T> {
@@ -855,6 +1005,42 @@ impl ActiveEventLoop {
         }
     }
 
+    #[cfg(wayland_platform)]
+    pub(crate) fn wl_seats(&self) -> Vec<std::ptr::NonNull<std::ffi::c_void>> {
+        match *self {
+            ActiveEventLoop::Wayland(ref evlp) => evlp.wl_seats(),
+            #[cfg(x11_platform)]
+            _ => Vec::new(),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    pub(crate) fn flush(&self) -> Result<(), ExternalError> {
+        match *self {
+            ActiveEventLoop::Wayland(ref evlp) => evlp.flush(),
+            #[cfg(x11_platform)]
+            _ => Err(ExternalError::NotSupported(NotSupportedError::new())),
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    pub(crate) fn compositor_info(&self) -> Option<crate::platform::wayland::CompositorInfo> {
+        match *self {
+            ActiveEventLoop::Wayland(ref evlp) => evlp.compositor_info(),
+            #[cfg(x11_platform)]
+            _ => None,
+        }
+    }
+
+    #[cfg(wayland_platform)]
+    pub(crate) fn capabilities(&self) -> crate::platform::wayland::Capabilities {
+        match *self {
+            ActiveEventLoop::Wayland(ref evlp) => evlp.capabilities(),
+            #[cfg(x11_platform)]
+            _ => Default::default(),
+        }
+    }
+
     pub fn create_custom_cursor(&self, cursor: CustomCursorSource) -> CustomCursor {
         x11_or_wayland!(match self; ActiveEventLoop(evlp) => evlp.create_custom_cursor(cursor))
     }
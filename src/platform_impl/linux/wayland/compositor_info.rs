@@ -0,0 +1,84 @@
+//! Telling which compositor the connection is to.
+
+use std::env;
+use std::fs;
+use std::os::unix::io::RawFd;
+
+use crate::platform::wayland::CompositorInfo;
+
+/// The info about the compositor on the other end of the `socket`.
+///
+/// The process is found from the credentials of the socket peer, falling back to the desktop
+/// from the environment when it can't be seen, like from a sandbox with its own process ids.
+pub fn compositor_info(socket: RawFd) -> Option<CompositorInfo> {
+    let name = peer_pid(socket)
+        .and_then(|pid| fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+        .and_then(|comm| process_name(&comm))
+        .or_else(|| {
+            env::var("XDG_CURRENT_DESKTOP")
+                .ok()
+                .and_then(|desktop| desktop_name(&desktop))
+        })?;
+
+    Some(CompositorInfo { name })
+}
+
+#[cfg(target_os = "linux")]
+fn peer_pid(socket: RawFd) -> Option<libc::pid_t> {
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            socket,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut _,
+            &mut len,
+        )
+    };
+
+    // The pid is zero when the peer is in another pid namespace.
+    (result == 0 && credentials.pid > 0).then_some(credentials.pid)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_pid(_socket: RawFd) -> Option<i32> {
+    None
+}
+
+/// The process name from the content of `/proc/<pid>/comm`.
+fn process_name(comm: &str) -> Option<String> {
+    let name = comm.trim_end_matches('\n');
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+/// The desktop from `XDG_CURRENT_DESKTOP`, which is a list like `ubuntu:GNOME` where the last
+/// one is the desktop the compositor comes with.
+fn desktop_name(desktop: &str) -> Option<String> {
+    desktop
+        .split(':')
+        .rfind(|name| !name.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(
+            process_name("kwin_wayland\n").as_deref(),
+            Some("kwin_wayland")
+        );
+        assert_eq!(process_name("\n"), None);
+
+        assert_eq!(desktop_name("ubuntu:GNOME").as_deref(), Some("GNOME"));
+        assert_eq!(desktop_name("sway").as_deref(), Some("sway"));
+        assert_eq!(desktop_name(""), None);
+    }
+}
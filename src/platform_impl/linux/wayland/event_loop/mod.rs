@@ -1,10 +1,12 @@
 //! The event-loop routines.
 
 use std::cell::{Cell, RefCell};
+use std::ffi::{c_void, CString};
 use std::io::Result as IOResult;
 use std::marker::PhantomData;
 use std::mem;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::ptr::NonNull;
 use std::rc::Rc;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
@@ -17,13 +19,17 @@ use sctk::reexports::client::{Connection, QueueHandle};
 
 use crate::cursor::OnlyCursorImage;
 use crate::dpi::LogicalSize;
-use crate::error::{EventLoopError, OsError as RootOsError};
+use crate::error::{EventLoopError, ExternalError, OsError as RootOsError};
 use crate::event::{Event, InnerSizeWriter, StartCause, WindowEvent};
 use crate::event_loop::{ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents};
 use crate::platform::pump_events::PumpStatus;
+use crate::platform::startup_notify;
+use crate::platform::wayland::{Capabilities, CompositorInfo};
+use crate::platform_impl::common::xkb::compose_locale;
 use crate::platform_impl::platform::min_timeout;
 use crate::platform_impl::{
     ActiveEventLoop as PlatformActiveEventLoop, OsError, PlatformCustomCursor,
+    PlatformSpecificEventLoopAttributes,
 };
 use crate::window::{CustomCursor as RootCustomCursor, CustomCursorSource};
 
@@ -33,8 +39,11 @@ pub mod sink;
 pub use proxy::EventLoopProxy;
 use sink::EventSink;
 
+use super::compositor_info;
+#[cfg(feature = "wayland-portal")]
+use super::portal::ColorSchemeWatcher;
+use super::scale_coalescing::ScaleCoalescing;
 use super::state::{WindowCompositorUpdate, WinitState};
-use super::window::state::FrameCallbackState;
 use super::{logical_to_physical_rounded, DeviceId, WaylandError, WindowId};
 
 type WaylandDispatcher = calloop::Dispatcher<'static, WaylandSource<WinitState>, WinitState>;
@@ -71,8 +80,18 @@ pub struct EventLoop<T: 'static> {
     event_loop: calloop::EventLoop<'static, WinitState>,
 }
 
+impl<T: 'static> Drop for EventLoop<T> {
+    fn drop(&mut self) {
+        // Tear the seats down while the connection is still around to send it.
+        self.with_state(|state| state.release_seats());
+        let _ = self.connection.flush();
+    }
+}
+
 impl<T: 'static> EventLoop<T> {
-    pub fn new() -> Result<EventLoop<T>, EventLoopError> {
+    pub fn new(
+        attributes: &mut PlatformSpecificEventLoopAttributes,
+    ) -> Result<EventLoop<T>, EventLoopError> {
         macro_rules! map_err {
             ($e:expr, $err:expr) => {
                 $e.map_err(|error| os_error!($err(error).into()))
@@ -94,6 +113,21 @@ impl<T: 'static> EventLoop<T> {
 
         let mut winit_state = WinitState::new(&globals, &queue_handle, event_loop.handle())
             .map_err(|error| os_error!(error))?;
+        winit_state.ime_commit_transform = attributes.ime_commit_transform.take();
+        winit_state.xkb_keymap =
+            attributes
+                .xkb_keymap
+                .take()
+                .and_then(|keymap| match CString::new(keymap) {
+                    Ok(keymap) => Some(keymap),
+                    Err(_) => {
+                        log::warn!("the custom xkb keymap contains a nul byte, ignoring it");
+                        None
+                    }
+                });
+        winit_state.compose_locale = compose_locale(attributes.compose_locale.take());
+        winit_state.scale_coalescing = attributes.scale_coalescing.map(ScaleCoalescing::new);
+        winit_state.startup_activation_token = startup_notify::wayland_token_from_env();
 
         // NOTE: do a roundtrip after binding the globals to prevent potential
         // races with the server.
@@ -160,6 +194,27 @@ impl<T: 'static> EventLoop<T> {
             .map_err(|error| error.error);
         map_err!(result, WaylandError::Calloop)?;
 
+        // Watch the color scheme preferred by the system, once there's a window.
+        #[cfg(feature = "wayland-portal")]
+        let (system_theme_sender, system_theme_channel) = calloop::channel::channel();
+        #[cfg(feature = "wayland-portal")]
+        let result = event_loop
+            .handle()
+            .insert_source(
+                system_theme_channel,
+                move |event, _, winit_state: &mut WinitState| {
+                    if let calloop::channel::Event::Msg(theme) = event {
+                        winit_state.system_theme_changed(theme);
+                    }
+                },
+            )
+            .map_err(|error| error.error);
+        #[cfg(feature = "wayland-portal")]
+        {
+            map_err!(result, WaylandError::Calloop)?;
+            winit_state.color_scheme_watcher = Some(ColorSchemeWatcher::new(system_theme_sender));
+        }
+
         let window_target = ActiveEventLoop {
             connection: connection.clone(),
             wayland_dispatcher: wayland_dispatcher.clone(),
@@ -468,6 +523,9 @@ impl<T: 'static> EventLoop<T> {
                 if window_requests.get(window_id).unwrap().take_closed() {
                     mem::drop(window_requests.remove(window_id));
                     mem::drop(state.windows.get_mut().remove(window_id));
+                    if let Some(coalescing) = state.scale_coalescing.as_mut() {
+                        coalescing.remove(*window_id);
+                    }
                     return Some(WindowEvent::Destroyed);
                 }
 
@@ -479,20 +537,39 @@ impl<T: 'static> EventLoop<T> {
                     .lock()
                     .unwrap();
 
-                if window.frame_callback_state() == FrameCallbackState::Requested {
+                // Presenting before the initial configure is a protocol error, so hold the redraw
+                // until it arrives, in case the window was created without it. It's also held
+                // while the window is suspended, since nothing would be shown.
+                if !window.can_redraw() {
                     return None;
                 }
 
-                // Reset the frame callbacks state.
-                window.frame_callback_reset();
-                let mut redraw_requested = window_requests
-                    .get(window_id)
-                    .unwrap()
-                    .take_redraw_requested();
+                // Hold the redraw until the frame rate limit allows it.
+                let now = Instant::now();
+                if let Some(delay) = window.redraw_delay(now) {
+                    let pending = window_requests
+                        .get(window_id)
+                        .unwrap()
+                        .redraw_requested
+                        .load(Ordering::Relaxed);
+                    mem::drop(window);
+                    if pending {
+                        state.defer_redraw(*window_id, delay);
+                    }
+                    return None;
+                }
+
+                // Take the redraw, which resets the frame callbacks state.
+                let mut redraw_requested =
+                    window.take_redraw(window_requests.get(window_id).unwrap());
 
                 // Redraw the frame while at it.
                 redraw_requested |= window.refresh_frame();
 
+                if redraw_requested {
+                    window.redraw_dispatched(now);
+                }
+
                 redraw_requested.then_some(WindowEvent::RedrawRequested)
             });
 
@@ -515,25 +592,35 @@ impl<T: 'static> EventLoop<T> {
         // This is always the last event we dispatch before poll again
         callback(Event::AboutToWait, &self.window_target);
 
-        // Update the window frames and schedule redraws.
+        // Update the window frames, send the IME state, animate the cursors, and schedule
+        // redraws.
         let mut wake_up = false;
         for window_id in window_ids.drain(..) {
-            wake_up |= self.with_state(|state| match state.windows.get_mut().get_mut(&window_id) {
-                Some(window) => {
-                    let refresh = window.lock().unwrap().refresh_frame();
-                    if refresh {
-                        state
-                            .window_requests
-                            .get_mut()
-                            .get_mut(&window_id)
-                            .unwrap()
-                            .redraw_requested
-                            .store(true, Ordering::Relaxed);
+            wake_up |= self.with_state(|state| {
+                let (refresh, cursor_changed) = match state.windows.get_mut().get_mut(&window_id) {
+                    Some(window) => {
+                        let mut window = window.lock().unwrap();
+                        window.flush_ime_state();
+                        (window.refresh_frame(), window.take_cursor_changed())
                     }
+                    None => return false,
+                };
 
-                    refresh
+                if cursor_changed {
+                    state.update_cursor_animation(window_id);
+                }
+
+                if refresh {
+                    state
+                        .window_requests
+                        .get_mut()
+                        .get_mut(&window_id)
+                        .unwrap()
+                        .redraw_requested
+                        .store(true, Ordering::Relaxed);
                 }
-                None => false,
+
+                refresh
             });
         }
 
@@ -690,6 +777,31 @@ impl ActiveEventLoop {
     #[inline]
     pub fn listen_device_events(&self, _allowed: DeviceEvents) {}
 
+    pub(crate) fn wl_seats(&self) -> Vec<NonNull<c_void>> {
+        use sctk::reexports::client::Proxy;
+
+        self.state
+            .borrow()
+            .seat_state
+            .seats()
+            .filter_map(|seat| NonNull::new(seat.id().as_ptr() as *mut _))
+            .collect()
+    }
+
+    pub(crate) fn capabilities(&self) -> Capabilities {
+        self.state.borrow().capabilities
+    }
+
+    pub(crate) fn compositor_info(&self) -> Option<CompositorInfo> {
+        compositor_info::compositor_info(self.connection.backend().poll_fd().as_raw_fd())
+    }
+
+    pub(crate) fn flush(&self) -> Result<(), ExternalError> {
+        self.connection
+            .flush()
+            .map_err(|error| ExternalError::Os(os_error!(WaylandError::Wire(error).into())))
+    }
+
     pub(crate) fn create_custom_cursor(&self, cursor: CustomCursorSource) -> RootCustomCursor {
         RootCustomCursor {
             inner: PlatformCustomCursor::Wayland(OnlyCursorImage(Arc::from(cursor.inner.0))),
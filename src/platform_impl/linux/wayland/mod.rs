@@ -16,8 +16,12 @@ pub use event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy};
 pub use output::{MonitorHandle, VideoModeHandle};
 pub use window::Window;
 
+mod compositor_info;
 mod event_loop;
 mod output;
+#[cfg(feature = "wayland-portal")]
+mod portal;
+mod scale_coalescing;
 mod seat;
 mod state;
 mod types;
@@ -0,0 +1,112 @@
+//! Querying the system preferences from the XDG desktop portal.
+
+use std::thread;
+
+use calloop::channel::Sender;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::window::Theme;
+
+const APPEARANCE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME: &str = "color-scheme";
+
+/// Watches the color scheme preferred by the system, sending it to the event loop.
+///
+/// Nothing happens until [`Self::start`], so an event loop without windows never connects to
+/// the session bus.
+pub struct ColorSchemeWatcher {
+    /// The sender to the event loop, until the watch is started.
+    sender: Option<Sender<Option<Theme>>>,
+}
+
+impl ColorSchemeWatcher {
+    pub fn new(sender: Sender<Option<Theme>>) -> Self {
+        Self {
+            sender: Some(sender),
+        }
+    }
+
+    /// Start watching the color scheme, unless it already is. The current color scheme is sent
+    /// once it's read.
+    ///
+    /// The watcher thread ends on the first change after the event loop is gone.
+    pub fn start(&mut self) {
+        let sender = match self.sender.take() {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let spawned = thread::Builder::new()
+            .name("winit color scheme watcher".into())
+            .spawn(move || {
+                if let Err(err) = watch(&sender) {
+                    log::info!("Failed to watch the color scheme: {err}");
+                }
+            });
+
+        if let Err(err) = spawned {
+            log::warn!("Failed to spawn the color scheme watcher: {err}");
+        }
+    }
+}
+
+/// Send the color scheme, and then every change of it, until the event loop is gone.
+fn watch(sender: &Sender<Option<Theme>>) -> zbus::Result<()> {
+    let connection = Connection::session()?;
+    let proxy = Proxy::new(
+        &connection,
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+    )?;
+
+    // Subscribe before reading, so no change is missed in between.
+    let signals = proxy.receive_signal("SettingChanged")?;
+    let color_scheme: OwnedValue = proxy.call("Read", &(APPEARANCE, COLOR_SCHEME))?;
+    if sender.send(parse_color_scheme(&color_scheme)).is_err() {
+        return Ok(());
+    }
+
+    for signal in signals {
+        let (namespace, key, value): (String, String, OwnedValue) = match signal.body() {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+
+        if namespace == APPEARANCE
+            && key == COLOR_SCHEME
+            && sender.send(parse_color_scheme(&value)).is_err()
+        {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the color scheme, which the older portals wrap in another variant.
+fn parse_color_scheme(value: &Value<'_>) -> Option<Theme> {
+    match value {
+        Value::Value(value) => parse_color_scheme(value),
+        Value::U32(1) => Some(Theme::Dark),
+        Value::U32(2) => Some(Theme::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_color_scheme() {
+        assert_eq!(parse_color_scheme(&Value::U32(0)), None);
+        assert_eq!(parse_color_scheme(&Value::U32(1)), Some(Theme::Dark));
+        assert_eq!(parse_color_scheme(&Value::U32(2)), Some(Theme::Light));
+        assert_eq!(parse_color_scheme(&Value::from("dark")), None);
+
+        let wrapped = Value::Value(Box::new(Value::U32(1)));
+        assert_eq!(parse_color_scheme(&wrapped), Some(Theme::Dark));
+    }
+}
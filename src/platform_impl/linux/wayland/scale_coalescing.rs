@@ -0,0 +1,98 @@
+//! Holding back the scale factor changes of the windows until they settle.
+
+use std::time::{Duration, Instant};
+
+use ahash::AHashMap;
+
+use super::WindowId;
+
+#[derive(Debug)]
+pub struct ScaleCoalescing {
+    /// How long the scale factor must stay the same to be settled.
+    delay: Duration,
+
+    /// The latest scale factor of the windows, with the time it settles at.
+    pending: AHashMap<WindowId, (f64, Instant)>,
+}
+
+impl ScaleCoalescing {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: Default::default(),
+        }
+    }
+
+    /// The scale factor of the window changed, which restarts its delay.
+    pub fn push(&mut self, window_id: WindowId, scale_factor: f64, now: Instant) {
+        self.pending
+            .insert(window_id, (scale_factor, now + self.delay));
+    }
+
+    /// Take the scale factors which settled by `now`.
+    pub fn settled(&mut self, now: Instant) -> Vec<(WindowId, f64)> {
+        let mut settled = Vec::new();
+        self.pending
+            .retain(|&window_id, &mut (scale_factor, deadline)| {
+                if deadline > now {
+                    return true;
+                }
+
+                settled.push((window_id, scale_factor));
+                false
+            });
+        settled
+    }
+
+    /// The time the next scale factor settles at.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|&(_, deadline)| deadline).min()
+    }
+
+    /// Forget the scale factor of a window, like when it's gone.
+    pub fn remove(&mut self, window_id: WindowId) {
+        self.pending.remove(&window_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settles_after_rapid_changes() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let window = WindowId::from(1);
+        let mut coalescing = ScaleCoalescing::new(Duration::from_millis(100));
+        assert_eq!(coalescing.next_deadline(), None);
+
+        // Dragged across the border of a 1x and a 2x output, entering and leaving them.
+        coalescing.push(window, 2., ms(0));
+        coalescing.push(window, 1., ms(10));
+        coalescing.push(window, 2., ms(20));
+        assert_eq!(coalescing.next_deadline(), Some(ms(120)));
+        assert_eq!(coalescing.settled(ms(110)), []);
+
+        // Only the last scale factor is reported, once.
+        assert_eq!(coalescing.settled(ms(120)), [(window, 2.)]);
+        assert_eq!(coalescing.settled(ms(500)), []);
+        assert_eq!(coalescing.next_deadline(), None);
+    }
+
+    #[test]
+    fn windows_settle_separately() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let (first, second) = (WindowId::from(1), WindowId::from(2));
+        let mut coalescing = ScaleCoalescing::new(Duration::from_millis(100));
+
+        coalescing.push(first, 2., ms(0));
+        coalescing.push(second, 1.5, ms(50));
+        assert_eq!(coalescing.settled(ms(100)), [(first, 2.)]);
+        assert_eq!(coalescing.next_deadline(), Some(ms(150)));
+
+        coalescing.remove(second);
+        assert_eq!(coalescing.settled(ms(150)), []);
+    }
+}
@@ -1,8 +1,10 @@
 //! The keyboard input handling.
 
-use std::sync::Mutex;
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use ahash::AHashMap;
 use calloop::timer::{TimeoutAction, Timer};
 use calloop::{LoopHandle, RegistrationToken};
 use log::warn;
@@ -21,6 +23,7 @@ use crate::platform_impl::common::xkb::Context;
 use crate::platform_impl::wayland::event_loop::sink::EventSink;
 use crate::platform_impl::wayland::seat::WinitSeatState;
 use crate::platform_impl::wayland::state::WinitState;
+use crate::platform_impl::wayland::window::WindowState;
 use crate::platform_impl::wayland::{self, DeviceId, WindowId};
 
 impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
@@ -45,7 +48,16 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     }
                     WlKeymapFormat::XkbV1 => {
                         let context = &mut seat_state.keyboard_state.as_mut().unwrap().xkb_context;
-                        context.set_keymap_from_fd(fd, size as usize);
+                        // The user provided keymap is compiled again every time the compositor
+                        // changes its keymap, so the state is reset the same way.
+                        match state.xkb_keymap.as_deref() {
+                            Some(keymap) if context.set_keymap_from_string(keymap) => (),
+                            Some(_) => {
+                                warn!("failed to compile the custom xkb keymap");
+                                context.set_keymap_from_fd(fd, size as usize);
+                            }
+                            None => context.set_keymap_from_fd(fd, size as usize),
+                        }
                     }
                     _ => unreachable!(),
                 },
@@ -138,6 +150,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
 
                 key_input(
                     seat_state,
+                    state.windows.get_mut(),
                     &mut state.events_sink,
                     data,
                     key,
@@ -189,6 +202,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
 
                         key_input(
                             seat_state,
+                            state.windows.get_mut(),
                             &mut state.events_sink,
                             data,
                             repeat_keycode,
@@ -213,6 +227,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
 
                 key_input(
                     seat_state,
+                    state.windows.get_mut(),
                     &mut state.events_sink,
                     data,
                     key,
@@ -250,6 +265,7 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
 
                 xkb_state.update_modifiers(mods_depressed, mods_latched, mods_locked, 0, 0, group);
                 seat_state.modifiers = xkb_state.modifiers().into();
+                let layout = xkb_context.take_layout_change();
 
                 // HACK: part of the workaround from `WlKeyboardEvent::Enter`.
                 let window_id = match *data.window_id.lock().unwrap() {
@@ -264,6 +280,12 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     WindowEvent::ModifiersChanged(seat_state.modifiers.into()),
                     window_id,
                 );
+
+                if let Some(layout) = layout {
+                    state
+                        .events_sink
+                        .push_window_event(WindowEvent::KeyboardLayoutChanged(layout), window_id);
+                }
             }
             WlKeyboardEvent::RepeatInfo { rate, delay } => {
                 let keyboard_state = seat_state.keyboard_state.as_mut().unwrap();
@@ -308,11 +330,15 @@ pub struct KeyboardState {
 }
 
 impl KeyboardState {
-    pub fn new(keyboard: WlKeyboard, loop_handle: LoopHandle<'static, WinitState>) -> Self {
+    pub fn new(
+        keyboard: WlKeyboard,
+        loop_handle: LoopHandle<'static, WinitState>,
+        compose_locale: Option<&CStr>,
+    ) -> Self {
         Self {
             keyboard,
             loop_handle,
-            xkb_context: Context::new().unwrap(),
+            xkb_context: Context::new(compose_locale).unwrap(),
             repeat_info: RepeatInfo::default(),
             repeat_token: None,
             current_repeat: None,
@@ -381,6 +407,7 @@ impl KeyboardData {
 
 fn key_input(
     seat_state: &mut WinitSeatState,
+    windows: &AHashMap<WindowId, Arc<Mutex<WindowState>>>,
     event_sink: &mut EventSink,
     data: &KeyboardData,
     keycode: u32,
@@ -397,6 +424,20 @@ fn key_input(
     let device_id = crate::event::DeviceId(crate::platform_impl::DeviceId::Wayland(DeviceId));
     if let Some(mut key_context) = keyboard_state.xkb_context.key_context() {
         let event = key_context.process_key_event(keycode, state, repeat);
+
+        // The key is still processed, to keep the keyboard state right.
+        let delivered = windows.get(&window_id).map_or(true, |window| {
+            let window = window.lock().unwrap();
+            key_delivered(
+                state,
+                window.ime_composing(),
+                window.ime_key_presses_while_composing(),
+            )
+        });
+        if !delivered {
+            return;
+        }
+
         let event = WindowEvent::KeyboardInput {
             device_id,
             event,
@@ -405,3 +446,30 @@ fn key_input(
         event_sink.push_window_event(event, window_id);
     }
 }
+
+/// Whether the key event is sent to the window, which could hold back the key presses while the
+/// IME is `composing`.
+fn key_delivered(state: ElementState, composing: bool, key_presses_while_composing: bool) -> bool {
+    state == ElementState::Released || !composing || key_presses_while_composing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_presses_while_composing() {
+        use ElementState::{Pressed, Released};
+
+        // By default, the keys the IME passes through are delivered.
+        assert!(key_delivered(Pressed, true, true));
+        assert!(key_delivered(Released, true, true));
+
+        // Otherwise only the releases are, so no key is left pressed.
+        assert!(!key_delivered(Pressed, true, false));
+        assert!(key_delivered(Released, true, false));
+
+        // Nothing is held back without a preedit.
+        assert!(key_delivered(Pressed, false, false));
+    }
+}
@@ -25,7 +25,9 @@ mod touch;
 
 pub use pointer::relative_pointer::RelativePointerState;
 pub use pointer::{PointerConstraintsState, WinitPointerData, WinitPointerDataExt};
-pub use text_input::{TextInputState, ZwpTextInputV3Ext};
+pub use text_input::{
+    commit_state, ImeEnablement, PendingTextInputState, SurroundingText, TextInputState,
+};
 
 use keyboard::{KeyboardData, KeyboardState};
 use text_input::TextInputData;
@@ -62,6 +64,50 @@ impl WinitSeatState {
     pub fn new() -> Self {
         Default::default()
     }
+
+    fn destroy_text_input(&mut self) {
+        if let Some(text_input) = self.text_input.take() {
+            text_input.destroy();
+        }
+    }
+
+    fn release_touch(&mut self) {
+        if let Some(touch) = self.touch.take() {
+            if touch.version() >= 3 {
+                touch.release();
+            }
+        }
+    }
+
+    /// Release the pointer, returning the id of its cursor surface.
+    fn release_pointer(&mut self) -> Option<ObjectId> {
+        if let Some(relative_pointer) = self.relative_pointer.take() {
+            relative_pointer.destroy();
+        }
+
+        let pointer = self.pointer.take()?;
+        let pointer_data = pointer.pointer().winit_data();
+
+        // Remove the inner locks/confines before dropping the pointer.
+        pointer_data.unlock_pointer();
+        pointer_data.unconfine_pointer();
+
+        if pointer.pointer().version() >= 3 {
+            pointer.pointer().release();
+        }
+
+        Some(pointer.surface().id())
+    }
+}
+
+impl Drop for WinitSeatState {
+    fn drop(&mut self) {
+        // The objects created from the others go first, so none outlives what it was created from.
+        self.destroy_text_input();
+        self.release_pointer();
+        self.release_touch();
+        self.keyboard_state = None;
+    }
 }
 
 impl SeatHandler for WinitState {
@@ -84,8 +130,11 @@ impl SeatHandler for WinitState {
             }
             SeatCapability::Keyboard if seat_state.keyboard_state.is_none() => {
                 let keyboard = seat.get_keyboard(queue_handle, KeyboardData::new(seat.clone()));
-                seat_state.keyboard_state =
-                    Some(KeyboardState::new(keyboard, self.loop_handle.clone()));
+                seat_state.keyboard_state = Some(KeyboardState::new(
+                    keyboard,
+                    self.loop_handle.clone(),
+                    self.compose_locale.as_deref(),
+                ));
             }
             SeatCapability::Pointer if seat_state.pointer.is_none() => {
                 let surface = self.compositor_state.create_surface(queue_handle);
@@ -143,39 +192,15 @@ impl SeatHandler for WinitState {
         seat: WlSeat,
         capability: SeatCapability,
     ) {
+        self.remove_text_input(&seat.id());
         let seat_state = self.seats.get_mut(&seat.id()).unwrap();
 
-        if let Some(text_input) = seat_state.text_input.take() {
-            text_input.destroy();
-        }
-
         match capability {
-            SeatCapability::Touch => {
-                if let Some(touch) = seat_state.touch.take() {
-                    if touch.version() >= 3 {
-                        touch.release();
-                    }
-                }
-            }
+            SeatCapability::Touch => seat_state.release_touch(),
             SeatCapability::Pointer => {
-                if let Some(relative_pointer) = seat_state.relative_pointer.take() {
-                    relative_pointer.destroy();
-                }
-
-                if let Some(pointer) = seat_state.pointer.take() {
-                    let pointer_data = pointer.pointer().winit_data();
-
-                    // Remove the cursor from the mapping.
-                    let surface_id = pointer.surface().id();
+                // Remove the cursor from the mapping.
+                if let Some(surface_id) = seat_state.release_pointer() {
                     let _ = self.pointer_surfaces.remove(&surface_id);
-
-                    // Remove the inner locks/confines before dropping the pointer.
-                    pointer_data.unlock_pointer();
-                    pointer_data.unconfine_pointer();
-
-                    if pointer.pointer().version() >= 3 {
-                        pointer.pointer().release();
-                    }
                 }
             }
             SeatCapability::Keyboard => {
@@ -201,12 +226,32 @@ impl SeatHandler for WinitState {
         _queue_handle: &QueueHandle<Self>,
         seat: WlSeat,
     ) {
-        let _ = self.seats.remove(&seat.id());
+        // The rest of the seat objects go away with its state.
+        self.remove_text_input(&seat.id());
+        if let Some(surface_id) = self
+            .seats
+            .remove(&seat.id())
+            .and_then(|mut seat_state| seat_state.release_pointer())
+        {
+            let _ = self.pointer_surfaces.remove(&surface_id);
+        }
         self.on_keyboard_destroy(&seat.id());
     }
 }
 
 impl WinitState {
+    /// Release the seats, after all the objects created from them.
+    pub fn release_seats(&mut self) {
+        self.seats.clear();
+        self.pointer_surfaces.clear();
+
+        for seat in self.seat_state.seats() {
+            if seat.version() >= 5 {
+                seat.release();
+            }
+        }
+    }
+
     fn on_keyboard_destroy(&mut self, seat: &ObjectId) {
         for (window_id, window) in self.windows.get_mut() {
             let mut window = window.lock().unwrap();
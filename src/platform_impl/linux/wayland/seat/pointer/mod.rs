@@ -5,7 +5,7 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use sctk::reexports::client::delegate_dispatch;
-use sctk::reexports::client::protocol::wl_pointer::WlPointer;
+use sctk::reexports::client::protocol::wl_pointer::{AxisSource, WlPointer};
 use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle, Dispatch};
@@ -24,7 +24,9 @@ use sctk::seat::pointer::{PointerEvent, PointerEventKind, PointerHandler};
 use sctk::seat::SeatState;
 
 use crate::dpi::{LogicalPosition, PhysicalPosition};
-use crate::event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use crate::event::{
+    ElementState, MouseButton, MouseScrollDelta, ScrollSource, TouchPhase, WindowEvent,
+};
 
 use crate::platform_impl::wayland::state::WinitState;
 use crate::platform_impl::wayland::{self, DeviceId, WindowId};
@@ -121,6 +123,7 @@ impl PointerHandler for WinitState {
                     if let Some(pointer) = seat_state.pointer.as_ref().map(Arc::downgrade) {
                         window.pointer_entered(pointer);
                     }
+                    window.pointer_moved(event.position.into());
 
                     // Set the currently focused surface.
                     pointer.winit_data().inner.lock().unwrap().surface = Some(window_id);
@@ -145,6 +148,7 @@ impl PointerHandler for WinitState {
                         .push_window_event(WindowEvent::CursorLeft { device_id }, window_id);
                 }
                 PointerEventKind::Motion { .. } => {
+                    window.pointer_moved(event.position.into());
                     self.events_sink.push_window_event(
                         WindowEvent::CursorMoved {
                             device_id,
@@ -181,27 +185,18 @@ impl PointerHandler for WinitState {
                 PointerEventKind::Axis {
                     horizontal,
                     vertical,
+                    source,
                     ..
                 } => {
                     // Get the current phase.
                     let mut pointer_data = pointer.winit_data().inner.lock().unwrap();
 
                     let has_discrete_scroll = horizontal.discrete != 0 || vertical.discrete != 0;
-
-                    // Figure out what to do about start/ended phases here.
-                    //
-                    // Figure out how to deal with `Started`. Also the `Ended` is not guaranteed
-                    // to be sent for mouse wheels.
-                    let phase = if horizontal.stop || vertical.stop {
-                        TouchPhase::Ended
-                    } else {
-                        match pointer_data.phase {
-                            // Discrete scroll only results in moved events.
-                            _ if has_discrete_scroll => TouchPhase::Moved,
-                            TouchPhase::Started | TouchPhase::Moved => TouchPhase::Moved,
-                            _ => TouchPhase::Started,
-                        }
-                    };
+                    let phase = scroll_phase(
+                        pointer_data.phase,
+                        has_discrete_scroll,
+                        horizontal.stop || vertical.stop,
+                    );
 
                     // Update the phase.
                     pointer_data.phase = phase;
@@ -227,6 +222,7 @@ impl PointerHandler for WinitState {
                             device_id,
                             delta,
                             phase,
+                            source: source.and_then(scroll_source),
                         },
                         window_id,
                     )
@@ -236,6 +232,34 @@ impl PointerHandler for WinitState {
     }
 }
 
+/// The phase of a scroll following the `previous` one.
+///
+/// The `stop` is the `axis_stop` event, which is sent once the fingers are lifted.
+fn scroll_phase(previous: TouchPhase, has_discrete_scroll: bool, stop: bool) -> TouchPhase {
+    // Figure out how to deal with `Started`. Also the `Ended` is not guaranteed
+    // to be sent for mouse wheels.
+    if stop {
+        TouchPhase::Ended
+    } else {
+        match previous {
+            // Discrete scroll only results in moved events.
+            _ if has_discrete_scroll => TouchPhase::Moved,
+            TouchPhase::Started | TouchPhase::Moved => TouchPhase::Moved,
+            _ => TouchPhase::Started,
+        }
+    }
+}
+
+fn scroll_source(source: AxisSource) -> Option<ScrollSource> {
+    match source {
+        AxisSource::Wheel => Some(ScrollSource::Wheel),
+        AxisSource::Finger => Some(ScrollSource::Finger),
+        AxisSource::Continuous => Some(ScrollSource::Continuous),
+        AxisSource::WheelTilt => Some(ScrollSource::WheelTilt),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct WinitPointerData {
     /// The inner winit data associated with the pointer.
@@ -503,3 +527,47 @@ delegate_dispatch!(WinitState: [ WpCursorShapeDeviceV1: GlobalData] => SeatState
 delegate_dispatch!(WinitState: [ZwpPointerConstraintsV1: GlobalData] => PointerConstraintsState);
 delegate_dispatch!(WinitState: [ZwpLockedPointerV1: GlobalData] => PointerConstraintsState);
 delegate_dispatch!(WinitState: [ZwpConfinedPointerV1: GlobalData] => PointerConstraintsState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finger_scroll_ends_on_stop() {
+        // The fingers scroll, then are lifted.
+        let mut phase = TouchPhase::Ended;
+        let mut phases = Vec::new();
+        for stop in [false, false, true] {
+            phase = scroll_phase(phase, false, stop);
+            phases.push(phase);
+        }
+        assert_eq!(
+            phases,
+            [TouchPhase::Started, TouchPhase::Moved, TouchPhase::Ended]
+        );
+        assert_eq!(
+            scroll_source(AxisSource::Finger),
+            Some(ScrollSource::Finger)
+        );
+
+        // The kinetic scroll has no stop, and can't be told apart by the phase alone.
+        assert_eq!(scroll_phase(phase, false, false), TouchPhase::Started);
+        assert_eq!(
+            scroll_source(AxisSource::Continuous),
+            Some(ScrollSource::Continuous)
+        );
+    }
+
+    #[test]
+    fn wheel_scroll_only_moves() {
+        assert_eq!(
+            scroll_phase(TouchPhase::Ended, true, false),
+            TouchPhase::Moved
+        );
+        assert_eq!(scroll_source(AxisSource::Wheel), Some(ScrollSource::Wheel));
+        assert_eq!(
+            scroll_source(AxisSource::WheelTilt),
+            Some(ScrollSource::WheelTilt)
+        );
+    }
+}
@@ -1,6 +1,12 @@
+use std::mem;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
+use bitflags::bitflags;
 use sctk::globals::GlobalData;
+use sctk::reexports::calloop::timer::{TimeoutAction, Timer};
+use sctk::reexports::client::backend::ObjectId;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 
 use sctk::reexports::client::delegate_dispatch;
@@ -10,18 +16,153 @@ use sctk::reexports::client::Dispatch;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::Event as TextInputEvent;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
-    ContentHint, ContentPurpose, ZwpTextInputV3,
+    ChangeCause, ContentHint, ContentPurpose, ZwpTextInputV3,
 };
 
 use crate::event::{Ime, WindowEvent};
 use crate::platform_impl::wayland;
+use crate::platform_impl::wayland::event_loop::sink::EventSink;
 use crate::platform_impl::wayland::state::WinitState;
-use crate::window::ImePurpose;
+use crate::platform_impl::wayland::window::WindowState;
+use crate::platform_impl::wayland::WindowId;
+use crate::window::{ImeContentHints, ImePurpose};
 
 pub struct TextInputState {
     text_input_manager: ZwpTextInputManagerV3,
 }
 
+impl WinitState {
+    /// Enter the text inputs on the window, which were focused on its surface before it was
+    /// registered.
+    pub fn text_input_window_registered(&mut self, window_id: WindowId) {
+        let window = match self.windows.get_mut().get(&window_id) {
+            Some(window) => window.clone(),
+            None => return,
+        };
+
+        for text_input in self
+            .seats
+            .values()
+            .filter_map(|seat| seat.text_input.as_ref())
+        {
+            let data = match text_input.data::<TextInputData>() {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let mut text_input_data = data.inner.lock().unwrap();
+            let entered = text_input_data
+                .surface
+                .as_ref()
+                .is_some_and(|surface| wayland::make_wid(surface) == window_id);
+            if entered {
+                enter_window(
+                    &mut self.events_sink,
+                    text_input,
+                    &mut text_input_data,
+                    window_id,
+                    &mut window.lock().unwrap(),
+                );
+            }
+        }
+    }
+
+    /// Destroy the text input of the `seat`, like when it lost the keyboard, removing it from the
+    /// windows it was on.
+    pub fn remove_text_input(&mut self, seat: &ObjectId) {
+        let text_input = match self
+            .seats
+            .get_mut(seat)
+            .and_then(|seat| seat.text_input.take())
+        {
+            Some(text_input) => text_input,
+            None => return,
+        };
+
+        for (window_id, window) in self.windows.get_mut() {
+            let mut window = window.lock().unwrap();
+
+            // IME stays enabled while the text input of another seat is on the window.
+            if !window.text_input_left(&text_input) || window.has_text_inputs() {
+                continue;
+            }
+
+            window.set_ime_composing(false);
+            let event = window
+                .ime_allowed()
+                .then_some(Ime::Disabled)
+                .and_then(|event| window.filter_ime_enablement(event));
+            if let Some(event) = event {
+                self.events_sink
+                    .push_window_event(WindowEvent::Ime(event), *window_id);
+            }
+        }
+
+        text_input.destroy();
+    }
+
+    /// Send the `Disabled` held back after the text input left the window at `deadline`, unless
+    /// the text input is back by then.
+    fn send_held_ime_disabled(&mut self, window_id: WindowId, deadline: Instant) {
+        if let Some(token) = self.held_ime_disables.remove(&window_id) {
+            self.loop_handle.remove(token);
+        }
+
+        let token =
+            self.loop_handle
+                .insert_source(Timer::from_deadline(deadline), move |_, _, state| {
+                    state.held_ime_disables.remove(&window_id);
+                    state.settle_ime_enablement(window_id, Instant::now());
+                    TimeoutAction::Drop
+                });
+
+        match token {
+            Ok(token) => {
+                self.held_ime_disables.insert(window_id, token);
+            }
+            Err(err) => {
+                log::warn!("Failed to hold back the IME disable: {err}");
+                self.settle_ime_enablement(window_id, deadline);
+            }
+        }
+    }
+
+    fn settle_ime_enablement(&mut self, window_id: WindowId, now: Instant) {
+        let event = self
+            .windows
+            .get_mut()
+            .get(&window_id)
+            .and_then(|window| window.lock().unwrap().settle_ime_enablement(now));
+        if let Some(event) = event {
+            self.events_sink
+                .push_window_event(WindowEvent::Ime(event), window_id);
+            self.dispatched_events = true;
+        }
+    }
+}
+
+/// Handle the text input entering the `window`.
+fn enter_window(
+    events_sink: &mut EventSink,
+    text_input: &ZwpTextInputV3,
+    text_input_data: &mut TextInputDataInner,
+    window_id: WindowId,
+    window: &mut WindowState,
+) {
+    // Regaining the focus, like on resume, enables the text input again. Otherwise, the next
+    // flush enables all the text inputs of the window.
+    if window.ime_text_inputs_enabled() {
+        window.enable_text_input(text_input);
+    }
+
+    let event = text_input_data.enter(window.ime_allowed());
+    if let Some(event) = event.and_then(|event| window.filter_ime_enablement(event)) {
+        events_sink.push_window_event(WindowEvent::Ime(event), window_id);
+    }
+
+    window.text_input_entered(text_input);
+}
+
 impl TextInputState {
     pub fn new(
         globals: &GlobalList,
@@ -66,127 +207,550 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
         match event {
             TextInputEvent::Enter { surface } => {
                 let window_id = wayland::make_wid(&surface);
-                text_input_data.surface = Some(surface);
 
-                let mut window = match windows.get(&window_id) {
-                    Some(window) => window.lock().unwrap(),
+                // The surface is kept, so the window still gets the text input once it's
+                // registered, see `WinitState::text_input_window_registered`.
+                let window = WinitState::window_for_surface(windows, &surface);
+                text_input_data.surface = Some(surface);
+                let mut window = match window {
+                    Some(window) => window,
                     None => return,
                 };
 
-                if window.ime_allowed() {
-                    text_input.enable();
-                    text_input.set_content_type_by_purpose(window.ime_purpose());
-                    text_input.commit();
-                    state
-                        .events_sink
-                        .push_window_event(WindowEvent::Ime(Ime::Enabled), window_id);
-                }
-
-                window.text_input_entered(text_input);
+                enter_window(
+                    &mut state.events_sink,
+                    text_input,
+                    &mut text_input_data,
+                    window_id,
+                    &mut window,
+                );
             }
             TextInputEvent::Leave { surface } => {
                 text_input_data.surface = None;
+                let composing = text_input_data.composing();
 
                 // Always issue a disable.
                 text_input.disable();
-                text_input.commit();
+                commit_state(text_input);
 
                 let window_id = wayland::make_wid(&surface);
-
-                // XXX this check is essential, because `leave` could have a
-                // reference to nil surface...
-                let mut window = match windows.get(&window_id) {
-                    Some(window) => window.lock().unwrap(),
+                let mut window = match WinitState::window_for_surface(windows, &surface) {
+                    Some(window) => window,
                     None => return,
                 };
 
                 window.text_input_left(text_input);
+                window.set_ime_composing(false);
 
-                state
-                    .events_sink
-                    .push_window_event(WindowEvent::Ime(Ime::Disabled), window_id);
+                // IME stays enabled while the text input of another seat is on the window. The
+                // `Disabled` is held back, so the focus bouncing back doesn't toggle IME.
+                let deadline = text_input_data
+                    .leave(window.ime_allowed())
+                    .filter(|_| !window.has_text_inputs())
+                    .and_then(|_| window.hold_ime_disabled(Instant::now()));
+                drop(window);
+
+                if let Some(deadline) = deadline {
+                    // The preedit is gone either way.
+                    if composing {
+                        let event = WindowEvent::Ime(Ime::Preedit(String::new(), None));
+                        state.events_sink.push_window_event(event, window_id);
+                    }
+
+                    state.send_held_ime_disabled(window_id, deadline);
+                }
             }
             TextInputEvent::PreeditString {
                 text,
                 cursor_begin,
                 cursor_end,
             } => {
-                let text = text.unwrap_or_default();
-                let cursor_begin = usize::try_from(cursor_begin)
-                    .ok()
-                    .and_then(|idx| text.is_char_boundary(idx).then_some(idx));
-                let cursor_end = usize::try_from(cursor_end)
-                    .ok()
-                    .and_then(|idx| text.is_char_boundary(idx).then_some(idx));
-
-                text_input_data.pending_preedit = Some(Preedit {
-                    text,
-                    cursor_begin,
-                    cursor_end,
-                })
+                text_input_data.preedit_string(text, cursor_begin, cursor_end);
             }
             TextInputEvent::CommitString { text } => {
-                text_input_data.pending_preedit = None;
-                text_input_data.pending_commit = text;
+                text_input_data.commit_string(text);
             }
-            TextInputEvent::Done { .. } => {
-                let window_id = match text_input_data.surface.as_ref() {
-                    Some(surface) => wayland::make_wid(surface),
+            TextInputEvent::Done { serial } => {
+                let surface = match text_input_data.surface.clone() {
+                    Some(surface) => surface,
                     None => return,
                 };
+                let window_id = wayland::make_wid(&surface);
 
-                // Clear preedit at the start of `Done`.
-                state.events_sink.push_window_event(
-                    WindowEvent::Ime(Ime::Preedit(String::new(), None)),
-                    window_id,
-                );
+                let (ime_active, clear_preedit, batched, surrounding_used) =
+                    match WinitState::window_for_surface(windows, &surface) {
+                        Some(mut window) => {
+                            if window.take_ime_preedit_discarded() {
+                                text_input_data.discard_preedit();
+                            }
 
-                // Send `Commit`.
-                if let Some(text) = text_input_data.pending_commit.take() {
-                    state
-                        .events_sink
-                        .push_window_event(WindowEvent::Ime(Ime::Commit(text)), window_id);
+                            let surrounding_used = text_input_data
+                                .edits_surrounding_text(window.ime_surrounding_selected());
+                            (
+                                window.ime_active(),
+                                window.ime_preedit_clear_on_done(),
+                                window.ime_batched_updates(),
+                                surrounding_used,
+                            )
+                        }
+                        None => return,
+                    };
+
+                // The compositor hasn't seen our last state yet.
+                if serial != data.commits.load(Ordering::Relaxed) {
+                    text_input_data.keep_stale_preedit();
                 }
 
-                // Send preedit.
-                if let Some(preedit) = text_input_data.pending_preedit.take() {
-                    let cursor_range = preedit
-                        .cursor_begin
-                        .map(|b| (b, preedit.cursor_end.unwrap_or(b)));
+                let events_sink = &mut state.events_sink;
+                let commit_transform = &mut state.ime_commit_transform;
+                let mut edited = false;
+                let mut batch = Vec::new();
+                text_input_data.done(ime_active, clear_preedit, |event| {
+                    edited |= matches!(event, Ime::Commit(_) | Ime::DeleteSurroundingText { .. });
+                    let event = match (event, commit_transform.as_mut()) {
+                        (Ime::Commit(text), Some(transform)) => Ime::Commit(transform(text)),
+                        (event, _) => event,
+                    };
+
+                    if batched {
+                        batch.push(event);
+                    } else {
+                        events_sink.push_window_event(WindowEvent::Ime(event), window_id);
+                    }
+                });
 
-                    state.events_sink.push_window_event(
-                        WindowEvent::Ime(Ime::Preedit(preedit.text, cursor_range)),
-                        window_id,
-                    );
+                if let Some(event) = batch_ime_events(batch) {
+                    events_sink.push_window_event(WindowEvent::Ime(event), window_id);
+                }
+
+                if ime_active && surrounding_used {
+                    let event = WindowEvent::Ime(Ime::SurroundingTextUsed);
+                    events_sink.push_window_event(event, window_id);
+                }
+
+                if let Some(mut window) = WinitState::window_for_surface(windows, &surface) {
+                    window.set_ime_composing(text_input_data.composing());
+                    if edited {
+                        window.ime_edited();
+                    }
                 }
             }
-            TextInputEvent::DeleteSurroundingText { .. } => {
-                // Not handled.
+            TextInputEvent::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => {
+                let surrounding_bounds = text_input_data
+                    .surface
+                    .as_ref()
+                    .and_then(|surface| WinitState::window_for_surface(windows, surface))
+                    .and_then(|window| {
+                        window.ime_surrounding_deletable(
+                            before_length as usize,
+                            after_length as usize,
+                        )
+                    });
+                text_input_data.delete_surrounding_text(
+                    before_length,
+                    after_length,
+                    surrounding_bounds,
+                );
+            }
+            event => log::warn!("unhandled text input event: {event:?}"),
+        }
+    }
+}
+
+/// Merge the IME `events` of a `done` into a single `Update`, or `None` when there are none.
+fn batch_ime_events(events: Vec<Ime>) -> Option<Ime> {
+    if events.is_empty() {
+        return None;
+    }
+
+    let mut delete = None;
+    let mut commit = None;
+    let mut preedit = (String::new(), None);
+    for event in events {
+        match event {
+            Ime::DeleteSurroundingText {
+                before_bytes,
+                after_bytes,
+            } => delete = Some((before_bytes, after_bytes)),
+            Ime::Commit(text) => commit = Some(text),
+            // The clear at the start is replaced by the preedit after the edits.
+            Ime::Preedit(text, cursor_range) => preedit = (text, cursor_range),
+            event => unreachable!("{event:?} sent by done"),
+        }
+    }
+
+    Some(Ime::Update {
+        delete_surrounding_text: delete,
+        commit,
+        preedit,
+    })
+}
+
+/// How long the `Disabled` of a `leave` is held back, so the focus coming back right away, like
+/// when it's stolen for a moment, doesn't disable and enable IME again.
+const FOCUS_BOUNCE: Duration = Duration::from_millis(100);
+
+/// The `Enabled` and `Disabled` sent to the user for a window, so they're only sent when IME
+/// actually changes, and not on every spurious `enter` and `leave` of the compositor.
+#[derive(Debug, Default)]
+pub struct ImeEnablement {
+    enabled: bool,
+
+    /// The time the `Disabled` held back after a `leave` is sent at.
+    held_disabled: Option<Instant>,
+}
+
+impl ImeEnablement {
+    /// The `event` to send, which is `None` for an `Enabled` or `Disabled` the user already has.
+    pub fn filter(&mut self, event: Ime) -> Option<Ime> {
+        match event {
+            Ime::Enabled | Ime::Disabled => {
+                // The held `Disabled` is replaced, so an `Enabled` right after it sends nothing.
+                self.held_disabled = None;
+
+                let enabled = event == Ime::Enabled;
+                (mem::replace(&mut self.enabled, enabled) != enabled).then_some(event)
             }
-            _ => {}
+            event => Some(event),
+        }
+    }
+
+    /// Hold the `Disabled` of a `leave` back, returning the time to send it at with
+    /// [`Self::settle`], or `None` when the user already has IME disabled.
+    pub fn hold_disabled(&mut self, now: Instant) -> Option<Instant> {
+        if !self.enabled {
+            return None;
+        }
+
+        let deadline = now + FOCUS_BOUNCE;
+        self.held_disabled = Some(deadline);
+        Some(deadline)
+    }
+
+    /// The held `Disabled` to send by `now`.
+    pub fn settle(&mut self, now: Instant) -> Option<Ime> {
+        match self.held_disabled {
+            Some(deadline) if deadline <= now => self.filter(Ime::Disabled),
+            _ => None,
         }
     }
 }
 
 pub trait ZwpTextInputV3Ext {
-    fn set_content_type_by_purpose(&self, purpose: ImePurpose);
+    fn set_content_type_by_purpose(&self, purpose: ImePurpose, hints: ImeContentHints);
 }
 
 impl ZwpTextInputV3Ext for ZwpTextInputV3 {
-    fn set_content_type_by_purpose(&self, purpose: ImePurpose) {
-        let (hint, purpose) = match purpose {
-            ImePurpose::Normal => (ContentHint::None, ContentPurpose::Normal),
-            ImePurpose::Password => (ContentHint::SensitiveData, ContentPurpose::Password),
-            ImePurpose::Terminal => (ContentHint::None, ContentPurpose::Terminal),
-        };
+    fn set_content_type_by_purpose(&self, purpose: ImePurpose, hints: ImeContentHints) {
+        let (hint, purpose) = content_type(purpose, hints);
         self.set_content_type(hint, purpose);
     }
 }
 
+/// The content type to send for the IME `purpose`, with the user's `hints` added to the ones
+/// of the purpose.
+///
+/// This is the only place the purposes are translated, so a new purpose has to be added here.
+fn content_type(purpose: ImePurpose, hints: ImeContentHints) -> (ContentHint, ContentPurpose) {
+    let (hint, purpose) = match purpose {
+        ImePurpose::Normal => (ContentHint::None, ContentPurpose::Normal),
+        ImePurpose::Password => (ContentHint::SensitiveData, ContentPurpose::Password),
+        ImePurpose::Terminal => (ContentHint::None, ContentPurpose::Terminal),
+        // Addresses are typed as they are, so no capitalization or spellcheck is asked.
+        ImePurpose::Url => (ContentHint::None, ContentPurpose::Url),
+        ImePurpose::Email => (ContentHint::None, ContentPurpose::Email),
+        ImePurpose::Number => (ContentHint::None, ContentPurpose::Number),
+        ImePurpose::Digits => (ContentHint::None, ContentPurpose::Digits),
+    };
+
+    let hint = [
+        (ImeContentHints::COMPLETION, ContentHint::Completion),
+        (ImeContentHints::SPELLCHECK, ContentHint::Spellcheck),
+        (
+            ImeContentHints::AUTO_CAPITALIZATION,
+            ContentHint::AutoCapitalization,
+        ),
+        (ImeContentHints::LOWERCASE, ContentHint::Lowercase),
+        (ImeContentHints::UPPERCASE, ContentHint::Uppercase),
+        (ImeContentHints::TITLECASE, ContentHint::Titlecase),
+        (ImeContentHints::LATIN, ContentHint::Latin),
+        (ImeContentHints::MULTILINE, ContentHint::Multiline),
+    ]
+    .into_iter()
+    .filter(|&(flag, _)| hints.contains(flag))
+    .fold(hint, |hint, (_, content_hint)| hint | content_hint);
+
+    (hint, purpose)
+}
+
+bitflags! {
+    /// The text input state which changed since it was last sent to the compositor.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    struct TextInputChanges: u8 {
+        const CONTENT_TYPE = 1 << 0;
+        const CURSOR_RECTANGLE = 1 << 1;
+        const SURROUNDING_TEXT = 1 << 2;
+    }
+}
+
+/// The most of the surrounding text the protocol allows to send at once.
+const MAX_SURROUNDING_TEXT_BYTES: usize = 4000;
+
+/// The text around the cursor, with the cursor and the anchor of the selection as byte offsets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurroundingText {
+    text: String,
+    cursor: usize,
+    anchor: usize,
+}
+
+impl SurroundingText {
+    /// `None` is returned when the cursor or the anchor isn't on a character boundary.
+    pub fn new(text: String, cursor: usize, anchor: usize) -> Option<Self> {
+        (text.is_char_boundary(cursor) && text.is_char_boundary(anchor)).then_some(Self {
+            text,
+            cursor,
+            anchor,
+        })
+    }
+
+    /// The bytes before and after the selection, or the cursor when nothing is selected, in the
+    /// text sent to the compositor.
+    pub fn bounds(&self) -> (usize, usize) {
+        let (text, cursor, anchor) = self.window();
+        (cursor.min(anchor), text.len() - cursor.max(anchor))
+    }
+
+    /// The most bytes, up to `before` and `after`, which could be deleted before and after the
+    /// selection in the text sent to the compositor without cutting a char.
+    pub fn deletable(&self, before: usize, after: usize) -> (usize, usize) {
+        let (max_before, max_after) = self.bounds();
+        let (text, cursor, anchor) = self.window();
+        let (start, end) = (cursor.min(anchor), cursor.max(anchor));
+
+        let mut delete_start = start - before.min(max_before);
+        while !text.is_char_boundary(delete_start) {
+            delete_start += 1;
+        }
+
+        let mut delete_end = end + after.min(max_after);
+        while !text.is_char_boundary(delete_end) {
+            delete_end -= 1;
+        }
+
+        (start - delete_start, delete_end - end)
+    }
+
+    /// The part of the text sent to the compositor, with the cursor and the anchor relative to it.
+    ///
+    /// The protocol limits the surrounding text to 4000 bytes, so a longer text is cut around the
+    /// selection, or around the cursor when the selection doesn't fit.
+    fn window(&self) -> (&str, usize, usize) {
+        let len = self.text.len();
+        if len <= MAX_SURROUNDING_TEXT_BYTES {
+            return (&self.text, self.cursor, self.anchor);
+        }
+
+        let (mut start, mut end) = (self.cursor.min(self.anchor), self.cursor.max(self.anchor));
+        if end - start > MAX_SURROUNDING_TEXT_BYTES {
+            (start, end) = (self.cursor, self.cursor);
+        }
+
+        // Share the room left evenly on both sides, giving what's past the end of the text to the
+        // other side.
+        let room = MAX_SURROUNDING_TEXT_BYTES - (end - start);
+        end = (end + room / 2).min(len);
+        start = start.saturating_sub(MAX_SURROUNDING_TEXT_BYTES - (end - start));
+        end = (start + MAX_SURROUNDING_TEXT_BYTES).min(len);
+
+        // The cursor and the anchor are on char boundaries, so the cuts never pass them.
+        while !self.text.is_char_boundary(start) {
+            start += 1;
+        }
+        while !self.text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let anchor = self.anchor.clamp(start, end);
+        (&self.text[start..end], self.cursor - start, anchor - start)
+    }
+}
+
+/// The text input state requested by the user and the part of it which wasn't sent to the
+/// compositor yet.
+///
+/// The changes are accumulated, so updating several parts of the state in quick succession
+/// results in a single `commit`.
+#[derive(Debug, Default)]
+pub struct PendingTextInputState {
+    changes: TextInputChanges,
+    purpose: ImePurpose,
+    hints: ImeContentHints,
+    cursor_rectangle: Option<(i32, i32, i32, i32)>,
+    surrounding_text: Option<SurroundingText>,
+
+    /// What changed the surrounding text the last time it was set.
+    surrounding_change: Option<ChangeCause>,
+
+    /// Whether the IME edited the text since the surrounding text was last set.
+    ime_edited: bool,
+
+    /// Whether the text inputs were enabled, as last sent to the compositor.
+    enabled: bool,
+}
+
+impl PendingTextInputState {
+    /// Whether the text inputs were enabled, as last sent to the compositor.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether the text inputs should be enabled or disabled for the IME to be `active`, if it
+    /// changed since the last call.
+    ///
+    /// Only the net change is returned, so toggling IME several times in between results in a
+    /// single request, or none at all.
+    pub fn take_enabled(&mut self, active: bool) -> Option<bool> {
+        (self.enabled != active).then(|| {
+            self.enabled = active;
+            active
+        })
+    }
+
+    /// Set the content type derived from the `purpose`.
+    pub fn set_content_type(&mut self, purpose: ImePurpose) {
+        self.purpose = purpose;
+        self.changes |= TextInputChanges::CONTENT_TYPE;
+    }
+
+    /// Set the content hints sent along the purpose.
+    pub fn set_content_hints(&mut self, hints: ImeContentHints) {
+        self.hints = hints;
+        self.changes |= TextInputChanges::CONTENT_TYPE;
+    }
+
+    /// Set the cursor rectangle in surface local coordinates.
+    pub fn set_cursor_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        self.cursor_rectangle = Some((x, y, width, height));
+        self.changes |= TextInputChanges::CURSOR_RECTANGLE;
+    }
+
+    /// Set the text around the cursor.
+    ///
+    /// The first surrounding text after the IME edited the text is the result of the edit, so
+    /// the input method is told it caused the change. Otherwise, the text was changed by the
+    /// user.
+    pub fn set_surrounding_text(&mut self, surrounding_text: SurroundingText) {
+        self.surrounding_text = Some(surrounding_text);
+        self.surrounding_change = Some(if mem::take(&mut self.ime_edited) {
+            ChangeCause::InputMethod
+        } else {
+            ChangeCause::Other
+        });
+        self.changes |= TextInputChanges::SURROUNDING_TEXT;
+    }
+
+    /// The IME committed or deleted text, which the next surrounding text reflects.
+    pub fn ime_edited(&mut self) {
+        self.ime_edited = true;
+    }
+
+    /// The most bytes, up to `before` and `after`, which could be deleted around the selection
+    /// in the surrounding text, if it was set.
+    pub fn surrounding_deletable(&self, before: usize, after: usize) -> Option<(usize, usize)> {
+        self.surrounding_text
+            .as_ref()
+            .map(|surrounding| surrounding.deletable(before, after))
+    }
+
+    /// Whether the surrounding text has a selection, if it was set.
+    pub fn surrounding_selected(&self) -> Option<bool> {
+        self.surrounding_text
+            .as_ref()
+            .map(|surrounding| surrounding.cursor != surrounding.anchor)
+    }
+
+    /// The whole requested state, to send when enabling the text input, since that resets it.
+    pub fn full_update(&self) -> TextInputUpdate {
+        TextInputUpdate {
+            content_type: Some((self.purpose, self.hints)),
+            cursor_rectangle: self.cursor_rectangle,
+            surrounding_text: self.surrounding(),
+        }
+    }
+
+    fn surrounding(&self) -> Option<(SurroundingText, ChangeCause)> {
+        self.surrounding_text.clone().zip(self.surrounding_change)
+    }
+
+    /// Take the changes accumulated since the last call, if any.
+    pub fn take(&mut self) -> Option<TextInputUpdate> {
+        let changes = mem::take(&mut self.changes);
+        (!changes.is_empty()).then(|| TextInputUpdate {
+            content_type: changes
+                .contains(TextInputChanges::CONTENT_TYPE)
+                .then_some((self.purpose, self.hints)),
+            cursor_rectangle: self
+                .cursor_rectangle
+                .filter(|_| changes.contains(TextInputChanges::CURSOR_RECTANGLE)),
+            surrounding_text: self
+                .surrounding()
+                .filter(|_| changes.contains(TextInputChanges::SURROUNDING_TEXT)),
+        })
+    }
+}
+
+/// The changed text input state, which is sent together with a single `commit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextInputUpdate {
+    content_type: Option<(ImePurpose, ImeContentHints)>,
+    cursor_rectangle: Option<(i32, i32, i32, i32)>,
+    surrounding_text: Option<(SurroundingText, ChangeCause)>,
+}
+
+impl TextInputUpdate {
+    /// Send the update to the `text_input`.
+    pub fn send(&self, text_input: &ZwpTextInputV3) {
+        if let Some((purpose, hints)) = self.content_type {
+            text_input.set_content_type_by_purpose(purpose, hints);
+        }
+
+        if let Some((x, y, width, height)) = self.cursor_rectangle {
+            text_input.set_cursor_rectangle(x, y, width, height);
+        }
+
+        if let Some((surrounding, cause)) = self.surrounding_text.as_ref() {
+            text_input.set_text_change_cause(*cause);
+            let (text, cursor, anchor) = surrounding.window();
+            text_input.set_surrounding_text(text.to_owned(), cursor as i32, anchor as i32);
+        }
+
+        commit_state(text_input);
+    }
+}
+
 /// The Data associated with the text input.
 #[derive(Default)]
 pub struct TextInputData {
     inner: std::sync::Mutex<TextInputDataInner>,
+
+    /// The number of commits made on the text input, which `done` refers to as its serial.
+    ///
+    /// It's outside of the inner data, since the window commits while holding its own lock.
+    commits: AtomicU32,
+}
+
+/// Commit the state of the text input, counting the commit so the `done` for it can be told
+/// apart from the stale ones.
+pub fn commit_state(text_input: &ZwpTextInputV3) {
+    if let Some(data) = text_input.data::<TextInputData>() {
+        data.commits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    text_input.commit();
 }
 
 #[derive(Default)]
@@ -199,14 +763,1141 @@ pub struct TextInputDataInner {
 
     /// The preedit to submit on `done`.
     pending_preedit: Option<Preedit>,
+
+    /// The bytes to delete before and after the cursor on `done`.
+    pending_delete: Option<(usize, usize)>,
+
+    /// The preedit which was last sent to the user, empty when there's none.
+    current_preedit: Preedit,
+}
+
+impl TextInputDataInner {
+    /// Handle the text input entering a surface, returning the event to send to the user.
+    ///
+    /// `Enabled` is only sent when the window allows IME, otherwise it'll be sent once the
+    /// user enables IME on the window.
+    fn enter(&mut self, ime_allowed: bool) -> Option<Ime> {
+        self.discard_pending();
+        ime_allowed.then_some(Ime::Enabled)
+    }
+
+    /// Handle the text input leaving a surface, returning the event to send to the user.
+    ///
+    /// `Disabled` is only sent when the window still allows IME, since otherwise the user either
+    /// never got `Enabled` or already got `Disabled` when disallowing IME.
+    fn leave(&mut self, ime_allowed: bool) -> Option<Ime> {
+        self.discard_pending();
+        self.current_preedit.clear();
+        ime_allowed.then_some(Ime::Disabled)
+    }
+
+    /// Drop the state the compositor never finished with a `done`, since it belongs to the
+    /// previous focus, so a misbehaving compositor can't leave a stuck preedit behind.
+    fn discard_pending(&mut self) {
+        self.pending_commit = None;
+        self.pending_preedit = None;
+        self.pending_delete = None;
+    }
+
+    /// Keep the preedit the user has, since the pending one was sent for a state which was
+    /// already replaced, so it could bring back the preedit which was just committed.
+    ///
+    /// The edits are still applied, since dropping them would lose text.
+    fn keep_stale_preedit(&mut self) {
+        let edited = self.pending_commit.is_some() || self.pending_delete.is_some();
+        self.pending_preedit = (!edited).then(|| self.current_preedit.clone());
+    }
+
+    /// Whether the edits applied on the next `done` likely used the surrounding text, given
+    /// whether it has a selection, or `None` when it wasn't set.
+    ///
+    /// A deletion is always made within the surrounding text, while a commit only replaces the
+    /// selection, like on reconversion.
+    fn edits_surrounding_text(&self, surrounding_selected: Option<bool>) -> bool {
+        surrounding_selected.is_some_and(|selected| {
+            self.pending_delete.is_some() || (selected && self.pending_commit.is_some())
+        })
+    }
+
+    /// Whether there's a preedit shown to the user.
+    fn composing(&self) -> bool {
+        !self.current_preedit.text.is_empty()
+    }
+
+    /// Forget the preedit, which the user was already told is gone.
+    fn discard_preedit(&mut self) {
+        self.current_preedit.clear();
+    }
+
+    /// Stash the preedit, which will be applied on the next `done`.
+    fn preedit_string(&mut self, text: Option<String>, cursor_begin: i32, cursor_end: i32) {
+        let text = text.unwrap_or_default();
+        let cursor_range = preedit_cursor_range(&text, cursor_begin, cursor_end);
+        self.pending_preedit = Some(Preedit { text, cursor_range })
+    }
+
+    /// Stash the deletion around the cursor, which will be applied on the next `done`.
+    ///
+    /// The deletion is clamped to the `surrounding_bounds` of the window, when it has the
+    /// surrounding text set, which also keep it from cutting a char.
+    fn delete_surrounding_text(
+        &mut self,
+        before_length: u32,
+        after_length: u32,
+        surrounding_bounds: Option<(usize, usize)>,
+    ) {
+        let mut before = before_length as usize;
+        let mut after = after_length as usize;
+
+        // The compositor can't know more text than we've told it about.
+        if let Some((max_before, max_after)) = surrounding_bounds {
+            before = before.min(max_before);
+            after = after.min(max_after);
+        }
+
+        self.pending_delete = Some((before, after));
+    }
+
+    /// Stash the commit, which will be applied on the next `done`.
+    ///
+    /// The preedit of the same `done` is kept whatever the order it came in, since it's always
+    /// applied after the commit. A `None` or empty text commits nothing, so it doesn't clear the
+    /// preedit it came along with. Like any `done`, the preedit is still gone when the `done`
+    /// has no preedit.
+    fn commit_string(&mut self, text: Option<String>) {
+        self.pending_commit = text.filter(|text| !text.is_empty());
+    }
+
+    /// Apply the pending state, passing the resulting events to `push` in the order they
+    /// should be delivered to the user.
+    ///
+    /// Nothing is sent when the window doesn't allow IME, so the user never gets `Preedit` or
+    /// `Commit` outside of `Enabled` and `Disabled`. The empty preedit is only sent when the user
+    /// has a preedit, and when `clear_preedit` is `false`, only when the preedit goes away instead
+    /// of at the start.
+    fn done(&mut self, ime_allowed: bool, clear_preedit: bool, mut push: impl FnMut(Ime)) {
+        let delete = self.pending_delete.take();
+        if !ime_allowed {
+            self.pending_commit = None;
+            self.pending_preedit = None;
+            self.current_preedit.clear();
+            return;
+        }
+
+        // Don't resend the preedit the user already has when nothing has changed.
+        let edited = self.pending_commit.is_some() || delete.is_some();
+        if !edited && self.pending_preedit.as_ref() == Some(&self.current_preedit) {
+            self.pending_preedit = None;
+            return;
+        }
+
+        // Clear preedit at the start of `Done`, the edits must always be right after the clear.
+        // There's nothing to clear when the user has no preedit.
+        let clear_preedit = (clear_preedit || edited) && self.composing();
+        if clear_preedit {
+            push(Ime::Preedit(String::new(), None));
+        }
+
+        // Whether the user already has no preedit, so an empty one isn't sent again.
+        let preedit_cleared = clear_preedit || self.current_preedit.text.is_empty();
+
+        // The text around the cursor is deleted before the commit is inserted.
+        if let Some((before_bytes, after_bytes)) = delete {
+            push(Ime::DeleteSurroundingText {
+                before_bytes,
+                after_bytes,
+            });
+        }
+
+        // Send `Commit`.
+        if let Some(text) = self.pending_commit.take() {
+            push(Ime::Commit(text));
+        }
+
+        // Send preedit.
+        match self.pending_preedit.take() {
+            Some(preedit) => {
+                self.current_preedit.set(&preedit);
+
+                if preedit.text.is_empty() && preedit_cleared {
+                    return;
+                }
+
+                push(Ime::Preedit(preedit.text, preedit.cursor_range));
+            }
+            None => {
+                // Let the user know that the preedit is gone when it wasn't cleared already.
+                if !clear_preedit && !self.current_preedit.text.is_empty() {
+                    push(Ime::Preedit(String::new(), None));
+                }
+
+                self.current_preedit.clear();
+            }
+        }
+    }
+}
+
+/// The cursor range of the preedit `text`, from the byte offsets sent by the compositor.
+///
+/// Both offsets being -1 hides the cursor. Offsets which aren't on a char boundary are dropped,
+/// and a reversed range is swapped, so the user can always slice the preedit with the range.
+fn preedit_cursor_range(text: &str, cursor_begin: i32, cursor_end: i32) -> Option<(usize, usize)> {
+    let offset = |idx: i32| {
+        usize::try_from(idx)
+            .ok()
+            .filter(|&idx| text.is_char_boundary(idx))
+    };
+
+    match (offset(cursor_begin), offset(cursor_end)) {
+        (Some(begin), Some(end)) if begin <= end => Some((begin, end)),
+        (Some(begin), Some(end)) => {
+            log::warn!("text input sent a reversed preedit cursor {cursor_begin}..{cursor_end}.");
+            Some((end, begin))
+        }
+        // Only the cursor position is known, so there's no selection.
+        (Some(begin), None) => {
+            log::warn!("text input sent an invalid preedit cursor end {cursor_end}.");
+            Some((begin, begin))
+        }
+        (None, Some(_)) => {
+            log::warn!("text input sent an invalid preedit cursor begin {cursor_begin}.");
+            None
+        }
+        (None, None) => {
+            if cursor_begin != -1 || cursor_end != -1 {
+                log::warn!(
+                    "text input sent an invalid preedit cursor {cursor_begin}..{cursor_end}."
+                );
+            }
+            None
+        }
+    }
 }
 
 /// The state of the preedit.
+#[derive(Default, Clone, PartialEq, Eq)]
 struct Preedit {
     text: String,
-    cursor_begin: Option<usize>,
-    cursor_end: Option<usize>,
+    cursor_range: Option<(usize, usize)>,
+}
+
+impl Preedit {
+    /// Reset the preedit, keeping the allocated buffer around.
+    fn clear(&mut self) {
+        self.text.clear();
+        self.cursor_range = None;
+    }
+
+    /// Copy the state from the `other` preedit reusing the allocated buffer.
+    fn set(&mut self, other: &Self) {
+        self.text.clone_from(&other.text);
+        self.cursor_range = other.cursor_range;
+    }
 }
 
 delegate_dispatch!(WinitState: [ZwpTextInputManagerV3: GlobalData] => TextInputState);
 delegate_dispatch!(WinitState: [ZwpTextInputV3: TextInputData] => TextInputState);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assert that the events follow
+    /// `Enabled -> (Preedit | DeleteSurroundingText | Commit)* -> Disabled`.
+    fn assert_ime_ordering(events: &[Ime]) {
+        let mut enabled = false;
+        for event in events {
+            match event {
+                Ime::Enabled => {
+                    assert!(!enabled, "`Enabled` sent twice in {events:?}");
+                    enabled = true;
+                }
+                Ime::Preedit(..)
+                | Ime::DeleteSurroundingText { .. }
+                | Ime::Commit(_)
+                | Ime::Update { .. }
+                | Ime::SurroundingTextUsed => {
+                    assert!(enabled, "{event:?} sent outside of `Enabled` in {events:?}")
+                }
+                Ime::Disabled => {
+                    assert!(enabled, "`Disabled` sent without `Enabled` in {events:?}");
+                    enabled = false;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ime_events_are_ordered() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, true, |event| events.push(event));
+        data.commit_string(Some("啊".into()));
+        data.done(true, true, |event| events.push(event));
+        events.extend(data.leave(true));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Preedit("a".into(), Some((1, 1))),
+                Ime::Preedit(String::new(), None),
+                Ime::Commit("啊".into()),
+                Ime::Disabled,
+            ]
+        );
+        assert_ime_ordering(&events);
+    }
+
+    #[test]
+    fn malformed_preedit_cursor() {
+        let text = "a啊b";
+
+        assert_eq!(preedit_cursor_range(text, 1, 4), Some((1, 4)));
+        assert_eq!(preedit_cursor_range(text, -1, -1), None);
+
+        // Reversed ranges are swapped.
+        assert_eq!(preedit_cursor_range(text, 4, 1), Some((1, 4)));
+
+        // Without a valid end, the cursor is collapsed at the beginning.
+        assert_eq!(preedit_cursor_range(text, 4, -1), Some((4, 4)));
+        assert_eq!(preedit_cursor_range(text, 4, 2), Some((4, 4)));
+        assert_eq!(preedit_cursor_range(text, 1, 10), Some((1, 1)));
+
+        // Without a valid beginning, the cursor is hidden.
+        assert_eq!(preedit_cursor_range(text, -1, 4), None);
+        assert_eq!(preedit_cursor_range(text, 2, 4), None);
+
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+        events.extend(data.enter(true));
+        data.preedit_string(Some(text.into()), 5, 0);
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [Ime::Enabled, Ime::Preedit(text.into(), Some((0, 5)))]
+        );
+    }
+
+    #[test]
+    fn batched_ime_events() {
+        let mut data = TextInputDataInner::default();
+        let batch = |data: &mut TextInputDataInner| {
+            let mut events = Vec::new();
+            data.done(true, true, |event| events.push(event));
+            batch_ime_events(events)
+        };
+
+        data.enter(true);
+        data.preedit_string(Some("ab".into()), 2, 2);
+        assert_eq!(
+            batch(&mut data),
+            Some(Ime::Update {
+                delete_surrounding_text: None,
+                commit: None,
+                preedit: ("ab".into(), Some((2, 2))),
+            })
+        );
+
+        // An autocorrection replacing the word, with a new preedit.
+        data.delete_surrounding_text(3, 0, None);
+        data.commit_string(Some("the".into()));
+        data.preedit_string(Some("c".into()), 1, 1);
+        assert_eq!(
+            batch(&mut data),
+            Some(Ime::Update {
+                delete_surrounding_text: Some((3, 0)),
+                commit: Some("the".into()),
+                preedit: ("c".into(), Some((1, 1))),
+            })
+        );
+
+        // The preedit going away.
+        data.preedit_string(None, -1, -1);
+        assert_eq!(
+            batch(&mut data),
+            Some(Ime::Update {
+                delete_surrounding_text: None,
+                commit: None,
+                preedit: (String::new(), None),
+            })
+        );
+
+        // Nothing changed.
+        data.preedit_string(None, -1, -1);
+        assert_eq!(batch(&mut data), None);
+    }
+
+    #[test]
+    fn empty_commit_keeps_preedit() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("ab".into()), 2, 2);
+        data.done(true, false, |event| events.push(event));
+
+        // Nothing is committed while composing.
+        for text in [None, Some(String::new())] {
+            data.commit_string(text);
+            data.preedit_string(Some("ab".into()), 2, 2);
+            data.done(true, false, |event| events.push(event));
+        }
+
+        // Without the preedit, it's gone.
+        data.commit_string(None);
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Preedit("ab".into(), Some((2, 2))),
+                Ime::Preedit(String::new(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_preedit_clear_without_preedit() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        // Typing latin text through the IME only commits.
+        events.extend(data.enter(true));
+        for text in ["a", "b", "c"] {
+            data.commit_string(Some(text.into()));
+            data.done(true, true, |event| events.push(event));
+        }
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Commit("a".into()),
+                Ime::Commit("b".into()),
+                Ime::Commit("c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ime_bypassed_during_chord() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("k".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+
+        // Ctrl is held, the window clears the preedit and the text input is disabled.
+        events.push(Ime::Preedit(String::new(), None));
+        data.discard_preedit();
+        data.commit_string(Some("k".into()));
+        data.done(false, false, |event| events.push(event));
+
+        // The IME is back once Ctrl is released, with the same preedit as before.
+        data.preedit_string(Some("k".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Preedit("k".into(), Some((1, 1))),
+                Ime::Preedit(String::new(), None),
+                Ime::Preedit("k".into(), Some((1, 1))),
+            ]
+        );
+        assert_ime_ordering(&events);
+    }
+
+    #[test]
+    fn commit_replaces_preedit_in_one_done() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("啊".into()), 3, 3);
+        data.done(true, false, |event| events.push(event));
+        events.clear();
+
+        // The final commit with the preedit cleared, the clear being sent first.
+        data.preedit_string(None, 0, 0);
+        data.commit_string(Some("啊".into()));
+        data.done(true, true, |event| events.push(event));
+        assert_eq!(
+            events,
+            [Ime::Preedit(String::new(), None), Ime::Commit("啊".into())]
+        );
+        events.clear();
+
+        // The next composition starting in the same `Done` as the commit.
+        data.preedit_string(Some("b".into()), 1, 1);
+        data.commit_string(Some("不".into()));
+        data.done(true, false, |event| events.push(event));
+        assert_eq!(
+            events,
+            [
+                Ime::Commit("不".into()),
+                Ime::Preedit("b".into(), Some((1, 1)))
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_preedit_ignored() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+
+        // Sent before the compositor got our last commit, nothing changes.
+        data.preedit_string(Some("ab".into()), 2, 2);
+        data.keep_stale_preedit();
+        data.done(true, false, |event| events.push(event));
+
+        // The stale commit is still inserted, without bringing back its preedit.
+        data.preedit_string(Some("ab".into()), 2, 2);
+        data.commit_string(Some("ab".into()));
+        data.preedit_string(Some("ab".into()), 2, 2);
+        data.keep_stale_preedit();
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Preedit("a".into(), Some((1, 1))),
+                Ime::Preedit(String::new(), None),
+                Ime::Commit("ab".into()),
+            ]
+        );
+        assert_ime_ordering(&events);
+    }
+
+    #[test]
+    fn surrounding_text_deleted_before_commit() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.commit_string(Some("teh ".into()));
+        data.done(true, false, |event| events.push(event));
+
+        // Autocorrection replaces the word before the cursor.
+        data.delete_surrounding_text(4, 0, None);
+        data.commit_string(Some("the ".into()));
+        data.done(true, false, |event| events.push(event));
+
+        // Nothing is deleted again on the next update.
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Commit("teh ".into()),
+                Ime::DeleteSurroundingText {
+                    before_bytes: 4,
+                    after_bytes: 0,
+                },
+                Ime::Commit("the ".into()),
+                Ime::Preedit("a".into(), Some((1, 1))),
+            ]
+        );
+        assert_ime_ordering(&events);
+    }
+
+    #[test]
+    fn content_type_by_purpose() {
+        assert_eq!(
+            content_type(ImePurpose::Password, ImeContentHints::empty()),
+            (ContentHint::SensitiveData, ContentPurpose::Password)
+        );
+        assert_eq!(
+            content_type(ImePurpose::Url, ImeContentHints::empty()).1,
+            ContentPurpose::Url
+        );
+        assert_eq!(
+            content_type(ImePurpose::Email, ImeContentHints::empty()).1,
+            ContentPurpose::Email
+        );
+        assert_eq!(
+            content_type(ImePurpose::Digits, ImeContentHints::empty()),
+            (ContentHint::None, ContentPurpose::Digits)
+        );
+
+        // The hints are added to the ones of the purpose.
+        let hints = ImeContentHints::SPELLCHECK | ImeContentHints::MULTILINE;
+        assert_eq!(
+            content_type(ImePurpose::Password, hints).0,
+            ContentHint::SensitiveData | ContentHint::Spellcheck | ContentHint::Multiline
+        );
+    }
+
+    #[test]
+    fn ime_enablement_sent_once() {
+        let mut enablement = ImeEnablement::default();
+
+        // A spurious `enter` and `leave` pair repeats the focus.
+        assert_eq!(enablement.filter(Ime::Disabled), None);
+        assert_eq!(enablement.filter(Ime::Enabled), Some(Ime::Enabled));
+        assert_eq!(enablement.filter(Ime::Enabled), None);
+        assert_eq!(
+            enablement.filter(Ime::Commit("a".into())),
+            Some(Ime::Commit("a".into()))
+        );
+        assert_eq!(enablement.filter(Ime::Disabled), Some(Ime::Disabled));
+        assert_eq!(enablement.filter(Ime::Disabled), None);
+        assert_eq!(enablement.filter(Ime::Enabled), Some(Ime::Enabled));
+    }
+
+    #[test]
+    fn focus_bounce_is_ignored() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut enablement = ImeEnablement::default();
+        let mut events = Vec::new();
+
+        events.extend(enablement.filter(Ime::Enabled));
+
+        // The focus is stolen and returned right away.
+        let deadline = enablement.hold_disabled(ms(0));
+        assert_eq!(deadline, Some(ms(100)));
+        events.extend(enablement.filter(Ime::Enabled));
+        events.extend(enablement.settle(ms(100)));
+        assert_eq!(events, [Ime::Enabled]);
+
+        // The focus is gone for good.
+        let deadline = enablement.hold_disabled(ms(200)).unwrap();
+        assert_eq!(enablement.settle(ms(250)), None);
+        assert_eq!(enablement.settle(deadline), Some(Ime::Disabled));
+        assert_eq!(enablement.settle(ms(500)), None);
+
+        // Nothing is held when the user already has IME disabled.
+        assert_eq!(enablement.hold_disabled(ms(600)), None);
+
+        // Disallowing IME while the `Disabled` is held sends it right away, once.
+        enablement.filter(Ime::Enabled);
+        enablement.hold_disabled(ms(700));
+        assert_eq!(enablement.filter(Ime::Disabled), Some(Ime::Disabled));
+        assert_eq!(enablement.settle(ms(800)), None);
+    }
+
+    #[test]
+    fn orphaned_state_is_discarded() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        // The compositor never sends `done` for the edits before switching the focus.
+        events.extend(data.enter(true));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.commit_string(Some("b".into()));
+        data.delete_surrounding_text(1, 0, None);
+        events.extend(data.leave(true));
+        events.extend(data.enter(true));
+        assert!(!data.composing());
+
+        // The next update only has what was sent after the focus.
+        data.preedit_string(Some("c".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Disabled,
+                Ime::Enabled,
+                Ime::Preedit("c".into(), Some((1, 1))),
+            ]
+        );
+        assert_ime_ordering(&events);
+    }
+
+    #[test]
+    fn surrounding_text_used_by_edits() {
+        let mut data = TextInputDataInner::default();
+
+        // Plain typing doesn't need the surrounding text.
+        data.commit_string(Some("a".into()));
+        assert!(!data.edits_surrounding_text(None));
+        assert!(!data.edits_surrounding_text(Some(false)));
+        // Reconversion replaces the selection.
+        assert!(data.edits_surrounding_text(Some(true)));
+        data.done(true, false, |_| ());
+
+        data.delete_surrounding_text(1, 0, None);
+        assert!(data.edits_surrounding_text(Some(false)));
+        // Without the surrounding text, the IME deleted blindly.
+        assert!(!data.edits_surrounding_text(None));
+        data.done(true, false, |_| ());
+
+        assert!(!data.edits_surrounding_text(Some(true)));
+    }
+
+    #[test]
+    fn surrounding_text_deletion_is_clamped() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        let surrounding = SurroundingText::new("abcdef".into(), 3, 5).unwrap();
+        assert_eq!(surrounding.bounds(), (3, 1));
+        data.delete_surrounding_text(u32::MAX, 2, Some(surrounding.bounds()));
+        data.done(true, true, |event| events.push(event));
+        assert_eq!(
+            events,
+            [Ime::DeleteSurroundingText {
+                before_bytes: 3,
+                after_bytes: 1,
+            }]
+        );
+
+        // Nothing is deleted when IME isn't allowed.
+        events.clear();
+        data.delete_surrounding_text(1, 1, None);
+        data.done(false, true, |event| events.push(event));
+        data.done(true, true, |event| events.push(event));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn no_ime_events_when_not_allowed() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(false));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(false, true, |event| events.push(event));
+        data.commit_string(Some("a".into()));
+        data.done(false, true, |event| events.push(event));
+        events.extend(data.leave(false));
+
+        assert!(events.is_empty(), "unexpected events {events:?}");
+    }
+
+    #[test]
+    fn no_ime_events_after_disallowing() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, true, |event| events.push(event));
+
+        // The user disallows IME, which sends `Disabled` from the window.
+        events.push(Ime::Disabled);
+        data.commit_string(Some("a".into()));
+        data.done(false, true, |event| events.push(event));
+        events.extend(data.leave(false));
+
+        // The user allows IME again, which sends `Enabled` from the window.
+        events.push(Ime::Enabled);
+        data.preedit_string(Some("b".into()), 1, 1);
+        data.done(true, true, |event| events.push(event));
+        events.extend(data.leave(true));
+
+        assert_ime_ordering(&events);
+        assert_eq!(events.last(), Some(&Ime::Disabled));
+        assert!(!events.contains(&Ime::Commit("a".into())));
+    }
+
+    #[test]
+    fn no_preedit_clear_when_disabled() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+        data.preedit_string(Some("ab".into()), 2, 2);
+        data.done(true, false, |event| events.push(event));
+
+        assert_eq!(
+            events,
+            [
+                Ime::Enabled,
+                Ime::Preedit("a".into(), Some((1, 1))),
+                Ime::Preedit("ab".into(), Some((2, 2))),
+            ]
+        );
+
+        // The preedit is still cleared when it goes away.
+        events.clear();
+        data.preedit_string(None, -1, -1);
+        data.done(true, false, |event| events.push(event));
+        assert_eq!(events, [Ime::Preedit(String::new(), None)]);
+
+        events.clear();
+        data.preedit_string(Some("a".into()), 1, 1);
+        data.done(true, false, |event| events.push(event));
+        data.commit_string(Some("a".into()));
+        data.done(true, false, |event| events.push(event));
+        assert_eq!(
+            events,
+            [
+                Ime::Preedit("a".into(), Some((1, 1))),
+                Ime::Preedit(String::new(), None),
+                Ime::Commit("a".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_input_changes_are_coalesced() {
+        let mut pending = PendingTextInputState::default();
+        assert_eq!(pending.take(), None);
+
+        pending.set_cursor_rectangle(0, 0, 10, 10);
+        pending.set_content_type(ImePurpose::Password);
+        pending.set_cursor_rectangle(5, 5, 10, 10);
+
+        // All the changes are sent with a single commit.
+        assert_eq!(
+            pending.take(),
+            Some(TextInputUpdate {
+                content_type: Some((ImePurpose::Password, ImeContentHints::empty())),
+                cursor_rectangle: Some((5, 5, 10, 10)),
+                surrounding_text: None,
+            })
+        );
+        assert_eq!(pending.take(), None);
+
+        pending.set_content_type(ImePurpose::Terminal);
+        assert_eq!(
+            pending.take(),
+            Some(TextInputUpdate {
+                content_type: Some((ImePurpose::Terminal, ImeContentHints::empty())),
+                cursor_rectangle: None,
+                surrounding_text: None,
+            })
+        );
+
+        // The hints are sent along the purpose, which stays.
+        pending.set_content_hints(ImeContentHints::LATIN);
+        assert_eq!(
+            pending.take().unwrap().content_type,
+            Some((ImePurpose::Terminal, ImeContentHints::LATIN))
+        );
+        assert_eq!(
+            pending.full_update().content_type,
+            Some((ImePurpose::Terminal, ImeContentHints::LATIN))
+        );
+    }
+
+    #[test]
+    fn ime_toggling_is_coalesced() {
+        let mut pending = PendingTextInputState::default();
+        assert_eq!(pending.take_enabled(false), None);
+
+        // IME allowed, disallowed, and allowed again before the flush only enables once.
+        assert_eq!(pending.take_enabled(true), Some(true));
+        assert_eq!(pending.take_enabled(true), None);
+        assert!(pending.enabled());
+
+        // Disallowed and allowed back before the flush sends nothing.
+        assert_eq!(pending.take_enabled(true), None);
+
+        assert_eq!(pending.take_enabled(false), Some(false));
+        assert!(!pending.enabled());
+    }
+
+    #[test]
+    fn surrounding_text_on_char_boundaries() {
+        assert_eq!(SurroundingText::new("日本".into(), 1, 1), None);
+        assert_eq!(SurroundingText::new("日本".into(), 3, 7), None);
+
+        let surrounding = SurroundingText::new("日本".into(), 6, 3).unwrap();
+        assert_eq!(surrounding.bounds(), (3, 0));
+
+        let mut pending = PendingTextInputState::default();
+        pending.set_surrounding_text(surrounding.clone());
+        assert_eq!(
+            pending.surrounding_deletable(usize::MAX, usize::MAX),
+            Some((3, 0))
+        );
+        assert_eq!(
+            pending.take(),
+            Some(TextInputUpdate {
+                content_type: None,
+                cursor_rectangle: None,
+                surrounding_text: Some((surrounding, ChangeCause::Other)),
+            })
+        );
+    }
+
+    #[test]
+    fn surrounding_deletion_keeps_chars_whole() {
+        let surrounding = SurroundingText::new("日本語".into(), 3, 6).unwrap();
+        assert_eq!(surrounding.deletable(3, 3), (3, 3));
+
+        // Half a char isn't deleted.
+        assert_eq!(surrounding.deletable(2, 4), (0, 3));
+        assert_eq!(surrounding.deletable(1, 1), (0, 0));
+
+        // Past the text.
+        assert_eq!(surrounding.deletable(usize::MAX, usize::MAX), (3, 3));
+    }
+
+    #[test]
+    fn surrounding_text_change_cause() {
+        let surrounding = SurroundingText::new("ab".into(), 2, 2).unwrap();
+        let cause = |pending: &mut PendingTextInputState| {
+            pending
+                .take()
+                .and_then(|update| update.surrounding_text)
+                .map(|(_, cause)| cause)
+        };
+
+        let mut pending = PendingTextInputState::default();
+        pending.set_surrounding_text(surrounding.clone());
+        assert_eq!(cause(&mut pending), Some(ChangeCause::Other));
+
+        // The text reflecting a commit was changed by the IME, but not the ones after it.
+        pending.ime_edited();
+        pending.set_surrounding_text(surrounding.clone());
+        assert_eq!(cause(&mut pending), Some(ChangeCause::InputMethod));
+        assert_eq!(
+            pending
+                .full_update()
+                .surrounding_text
+                .map(|(_, cause)| cause),
+            Some(ChangeCause::InputMethod)
+        );
+        pending.set_surrounding_text(surrounding);
+        assert_eq!(cause(&mut pending), Some(ChangeCause::Other));
+    }
+
+    #[test]
+    fn long_surrounding_text_cut_around_cursor() {
+        let document = "a".repeat(10_000);
+
+        // The cursor at the very end of the document.
+        let surrounding = SurroundingText::new(document.clone(), 10_000, 10_000).unwrap();
+        let (text, cursor, anchor) = surrounding.window();
+        assert_eq!((text.len(), cursor, anchor), (4000, 4000, 4000));
+        assert_eq!(surrounding.bounds(), (4000, 0));
+
+        // In the middle, with a selection.
+        let surrounding = SurroundingText::new(document.clone(), 5100, 4900).unwrap();
+        let (text, cursor, anchor) = surrounding.window();
+        assert_eq!((text.len(), cursor, anchor), (4000, 2100, 1900));
+
+        // A selection too long to send is cut around the cursor.
+        let surrounding = SurroundingText::new(document, 9000, 0).unwrap();
+        let (text, cursor, anchor) = surrounding.window();
+        assert_eq!((text.len(), cursor, anchor), (4000, 3000, 0));
+    }
+
+    #[test]
+    fn surrounding_text_cut_on_char_boundaries() {
+        // Three bytes long characters, so the exact cut would split them.
+        let document = "日".repeat(3000);
+
+        let surrounding = SurroundingText::new(document.clone(), 4500, 4500).unwrap();
+        let (text, cursor, anchor) = surrounding.window();
+        assert!(text.len() <= 4000);
+        assert!(text.chars().all(|c| c == '日'));
+        assert_eq!(cursor, anchor);
+        assert_eq!(&text[..cursor], &document[4500 - cursor..4500]);
+
+        let surrounding = SurroundingText::new(document, 9000, 9000).unwrap();
+        let (text, cursor, _) = surrounding.window();
+        assert_eq!((text.len(), cursor), (3999, 3999));
+    }
+
+    #[test]
+    fn ime_restored_after_refocus() {
+        let mut data = TextInputDataInner::default();
+        let mut pending = PendingTextInputState::default();
+        let mut events = Vec::new();
+
+        events.extend(data.enter(true));
+        pending.set_content_type(ImePurpose::Terminal);
+        pending.set_cursor_rectangle(1, 2, 3, 4);
+        assert!(pending.take().is_some());
+
+        // Suspending the window moves the focus away, and resuming it brings the focus back.
+        events.extend(data.leave(true));
+        events.extend(data.enter(true));
+
+        assert_eq!(events, [Ime::Enabled, Ime::Disabled, Ime::Enabled]);
+
+        // The state sent before is sent again, since enabling the text input resets it.
+        assert_eq!(pending.take(), None);
+        assert_eq!(
+            pending.full_update(),
+            TextInputUpdate {
+                content_type: Some((ImePurpose::Terminal, ImeContentHints::empty())),
+                cursor_rectangle: Some((1, 2, 3, 4)),
+                surrounding_text: None,
+            }
+        );
+    }
+
+    /// A small xorshift generator, so the arbitrary inputs are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, max: u64) -> u64 {
+            self.next() % max
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len() as u64) as usize]
+        }
+
+        /// An index which is usually around the text, but could be anywhere.
+        fn index(&mut self, len: usize) -> i32 {
+            match self.below(4) {
+                0 => -(self.below(5) as i32) - 1,
+                1 => i32::MAX - self.below(2) as i32,
+                _ => self.below(len as u64 + 2) as i32,
+            }
+        }
+    }
+
+    const TEXTS: &[&str] = &["", "a", "日本", "é", "👩‍👩‍👧", "a\u{301}b", "\0"];
+
+    /// The protocol doesn't forbid any of the values, so they must never make us panic or send
+    /// invalid events.
+    fn check_events(events: &[Ime], bounds: Option<(usize, usize)>) {
+        assert_ime_ordering(events);
+        for event in events {
+            match event {
+                Ime::Preedit(text, Some((begin, end))) => {
+                    assert!(text.is_char_boundary(*begin), "{event:?}");
+                    assert!(text.is_char_boundary(*end), "{event:?}");
+                }
+                Ime::DeleteSurroundingText {
+                    before_bytes,
+                    after_bytes,
+                } => {
+                    if let Some((before, after)) = bounds {
+                        assert!(
+                            *before_bytes <= before && *after_bytes <= after,
+                            "{event:?}"
+                        );
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Feed the text input events in whatever order the compositor could send them.
+    fn feed_arbitrary_events(rng: &mut Rng, steps: usize) {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+        let ime_allowed = rng.below(4) != 0;
+        let bounds = (rng.below(2) == 0).then(|| (rng.below(8) as usize, rng.below(8) as usize));
+        let mut entered = false;
+
+        for _ in 0..steps {
+            match rng.below(8) {
+                0 if !entered => {
+                    entered = true;
+                    events.extend(data.enter(ime_allowed));
+                }
+                1 if entered => {
+                    entered = false;
+                    events.extend(data.leave(ime_allowed));
+                }
+                2 => {
+                    let text = rng.pick(TEXTS);
+                    let (begin, end) = (rng.index(text.len()), rng.index(text.len()));
+                    let text = (rng.below(4) != 0).then(|| text.to_owned());
+                    data.preedit_string(text, begin, end);
+                }
+                3 => {
+                    let text = (rng.below(4) != 0).then(|| rng.pick(TEXTS).to_owned());
+                    data.commit_string(text);
+                }
+                4 => {
+                    let before = rng.index(8) as u32;
+                    let after = rng.index(8) as u32;
+                    data.delete_surrounding_text(before, after, bounds);
+                }
+                5 => data.keep_stale_preedit(),
+                6 => data.discard_preedit(),
+                _ if entered => {
+                    let clear_preedit = rng.below(2) == 0;
+                    data.done(ime_allowed, clear_preedit, |event| events.push(event));
+                }
+                _ => (),
+            }
+        }
+
+        check_events(&events, bounds);
+    }
+
+    #[test]
+    fn arbitrary_text_input_events() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..2000 {
+            feed_arbitrary_events(&mut rng, 64);
+        }
+    }
+
+    #[test]
+    fn tricky_text_input_events() {
+        let mut data = TextInputDataInner::default();
+        let mut events = Vec::new();
+        events.extend(data.enter(true));
+
+        // The cursor inside a multi-byte character, out of bounds, and negative.
+        for (begin, end) in [(1, 2), (7, 7), (-1, -1), (i32::MIN, i32::MAX)] {
+            data.preedit_string(Some("日本".into()), begin, end);
+            data.done(true, false, |event| events.push(event));
+        }
+
+        // The deletions wrapping around when converted.
+        data.delete_surrounding_text(u32::MAX, u32::MAX, Some((2, 0)));
+        data.commit_string(Some(String::new()));
+        data.done(true, true, |event| events.push(event));
+
+        check_events(&events, Some((2, 0)));
+        assert!(events.contains(&Ime::DeleteSurroundingText {
+            before_bytes: 2,
+            after_bytes: 0
+        }));
+    }
+
+    #[test]
+    fn arbitrary_surrounding_text() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..500 {
+            let text = rng.pick(TEXTS).repeat(rng.below(2000) as usize);
+            let cursor = rng.below(text.len() as u64 + 1) as usize;
+            let anchor = rng.below(text.len() as u64 + 1) as usize;
+            let surrounding = match SurroundingText::new(text, cursor, anchor) {
+                Some(surrounding) => surrounding,
+                None => continue,
+            };
+
+            let (text, cursor, anchor) = surrounding.window();
+            assert!(text.len() <= MAX_SURROUNDING_TEXT_BYTES);
+            assert!(text.is_char_boundary(cursor) && text.is_char_boundary(anchor));
+
+            // The bounds delimit the selection in the window.
+            let (before, after) = surrounding.bounds();
+            assert!(before + after <= text.len());
+            let (start, end) = (before, text.len() - after);
+            assert!(text.is_char_boundary(start) && text.is_char_boundary(end));
+            assert!((start..=end).contains(&cursor) && (start..=end).contains(&anchor));
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use std::cell::RefCell;
+use std::ffi::CString;
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use ahash::AHashMap;
 
-use sctk::reexports::calloop::LoopHandle;
+use sctk::reexports::calloop::timer::{TimeoutAction, Timer};
+use sctk::reexports::calloop::{LoopHandle, RegistrationToken};
 use sctk::reexports::client::backend::ObjectId;
 use sctk::reexports::client::globals::GlobalList;
 use sctk::reexports::client::protocol::wl_output::WlOutput;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
+use sctk::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
+use sctk::reexports::protocols::xdg::decoration::zv1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1;
 
 use sctk::compositor::{CompositorHandler, CompositorState};
 use sctk::output::{OutputHandler, OutputState};
@@ -23,12 +28,18 @@ use sctk::shm::slot::SlotPool;
 use sctk::shm::{Shm, ShmHandler};
 use sctk::subcompositor::SubcompositorState;
 
+use crate::event::WindowEvent;
+use crate::platform::wayland::Capabilities;
 use crate::platform_impl::wayland::event_loop::sink::EventSink;
 use crate::platform_impl::wayland::output::MonitorHandle;
+#[cfg(feature = "wayland-portal")]
+use crate::platform_impl::wayland::portal::ColorSchemeWatcher;
+use crate::platform_impl::wayland::scale_coalescing::ScaleCoalescing;
 use crate::platform_impl::wayland::seat::{
     PointerConstraintsState, RelativePointerState, TextInputState, WinitPointerData,
     WinitPointerDataExt, WinitSeatState,
 };
+use crate::platform_impl::wayland::types::cursor::CursorThemes;
 use crate::platform_impl::wayland::types::kwin_blur::KWinBlurManager;
 use crate::platform_impl::wayland::types::wp_fractional_scaling::FractionalScalingManager;
 use crate::platform_impl::wayland::types::wp_viewporter::ViewporterState;
@@ -36,6 +47,7 @@ use crate::platform_impl::wayland::types::xdg_activation::XdgActivationState;
 use crate::platform_impl::wayland::window::{WindowRequests, WindowState};
 use crate::platform_impl::wayland::{WaylandError, WindowId};
 use crate::platform_impl::OsError;
+use crate::window::{ActivationToken, Theme};
 
 /// Winit's Wayland state.
 pub struct WinitState {
@@ -94,6 +106,25 @@ pub struct WinitState {
     /// Xdg activation.
     pub xdg_activation: Option<XdgActivationState>,
 
+    /// The transform applied to the IME commit text before it's sent to the user.
+    pub ime_commit_transform: Option<Box<dyn FnMut(String) -> String>>,
+
+    /// The keymap used instead of the one sent by the compositor.
+    pub xkb_keymap: Option<CString>,
+
+    /// The locale to load the compose table for, instead of the one from the environment.
+    pub compose_locale: Option<CString>,
+
+    /// The color scheme preferred by the system.
+    pub system_theme: Option<Theme>,
+
+    /// The watcher of the color scheme preferred by the system, started with the first window.
+    #[cfg(feature = "wayland-portal")]
+    pub color_scheme_watcher: Option<ColorSchemeWatcher>,
+
+    /// The activation token the application was launched with, used by the first window.
+    pub startup_activation_token: Option<ActivationToken>,
+
     /// Relative pointer.
     pub relative_pointer: Option<RelativePointerState>,
 
@@ -109,6 +140,27 @@ pub struct WinitState {
     /// KWin blur manager.
     pub kwin_blur_manager: Option<KWinBlurManager>,
 
+    /// The themes to animate the cursors with, when the compositor doesn't do that.
+    pub cursor_themes: Option<CursorThemes>,
+
+    /// The timers animating the cursors of the windows.
+    pub cursor_animations: AHashMap<WindowId, RegistrationToken>,
+
+    /// The timers waking up the loop for the redraws deferred by the frame rate limit.
+    pub deferred_redraws: AHashMap<WindowId, RegistrationToken>,
+
+    /// The timers sending the IME disables held back after the text inputs left the windows.
+    pub held_ime_disables: AHashMap<WindowId, RegistrationToken>,
+
+    /// The scale factor changes held back until they settle, when asked by the user.
+    pub scale_coalescing: Option<ScaleCoalescing>,
+
+    /// The timer applying the settled scale factors.
+    pub scale_coalescing_timer: Option<RegistrationToken>,
+
+    /// The optional protocols bound on the compositor.
+    pub capabilities: Capabilities,
+
     /// Loop handle to re-register event sources, such as keyboard repeat.
     pub loop_handle: LoopHandle<'static, Self>,
 
@@ -158,6 +210,37 @@ impl WinitState {
         let shm = Shm::bind(globals, queue_handle).map_err(WaylandError::Bind)?;
         let custom_cursor_pool = Arc::new(Mutex::new(SlotPool::new(2, &shm).unwrap()));
 
+        // The compositor animates the cursors set with the cursor shape protocol.
+        let has_global = |interface: &str| {
+            globals
+                .contents()
+                .with_list(|globals| globals.iter().any(|global| global.interface == interface))
+        };
+        let cursor_shape = has_global(WpCursorShapeManagerV1::interface().name);
+        let cursor_themes = (!cursor_shape).then(|| CursorThemes::new(shm.wl_shm().clone()));
+
+        let xdg_activation = XdgActivationState::bind(globals, queue_handle).ok();
+        let kwin_blur_manager = KWinBlurManager::new(globals, queue_handle).ok();
+        let text_input_state = TextInputState::new(globals, queue_handle)
+            .map_err(|e| log::warn!("Text input protocol not available, IME is unsupported: {e:?}"))
+            .ok();
+        let relative_pointer = RelativePointerState::new(globals, queue_handle).ok();
+        let pointer_constraints = PointerConstraintsState::new(globals, queue_handle)
+            .map(Arc::new)
+            .ok();
+
+        let capabilities = Capabilities {
+            fractional_scale: fractional_scaling_manager.is_some() && viewporter_state.is_some(),
+            activation: xdg_activation.is_some(),
+            pointer_constraints: pointer_constraints.is_some(),
+            relative_pointer: relative_pointer.is_some(),
+            text_input: text_input_state.is_some(),
+            server_side_decorations: has_global(ZxdgDecorationManagerV1::interface().name),
+            client_side_decorations: subcompositor_state.is_some(),
+            cursor_shape,
+            blur: kwin_blur_manager.is_some(),
+        };
+
         Ok(Self {
             registry_state,
             compositor_state: Arc::new(compositor_state),
@@ -166,9 +249,23 @@ impl WinitState {
             seat_state,
             shm,
             custom_cursor_pool,
+            cursor_themes,
+            cursor_animations: Default::default(),
+            deferred_redraws: Default::default(),
+            held_ime_disables: Default::default(),
+            scale_coalescing: None,
+            scale_coalescing_timer: None,
+            capabilities,
 
             xdg_shell: XdgShell::bind(globals, queue_handle).map_err(WaylandError::Bind)?,
-            xdg_activation: XdgActivationState::bind(globals, queue_handle).ok(),
+            xdg_activation,
+            ime_commit_transform: None,
+            xkb_keymap: None,
+            compose_locale: None,
+            system_theme: None,
+            #[cfg(feature = "wayland-portal")]
+            color_scheme_watcher: None,
+            startup_activation_token: None,
 
             windows: Default::default(),
             window_requests: Default::default(),
@@ -176,15 +273,13 @@ impl WinitState {
             window_events_sink: Default::default(),
             viewporter_state,
             fractional_scaling_manager,
-            kwin_blur_manager: KWinBlurManager::new(globals, queue_handle).ok(),
+            kwin_blur_manager,
 
             seats,
-            text_input_state: TextInputState::new(globals, queue_handle).ok(),
+            text_input_state,
 
-            relative_pointer: RelativePointerState::new(globals, queue_handle).ok(),
-            pointer_constraints: PointerConstraintsState::new(globals, queue_handle)
-                .map(Arc::new)
-                .ok(),
+            relative_pointer,
+            pointer_constraints,
             pointer_surfaces: Default::default(),
 
             monitors: Arc::new(Mutex::new(monitors)),
@@ -204,28 +299,19 @@ impl WinitState {
         // Check if the cursor surface.
         let window_id = super::make_wid(surface);
 
-        if let Some(window) = self.windows.get_mut().get(&window_id) {
+        if self.windows.get_mut().contains_key(&window_id) {
             // Don't update the scaling factor, when legacy method is used.
             if is_legacy && self.fractional_scaling_manager.is_some() {
                 return;
             }
 
-            // The scale factor change is for the window.
-            let pos = if let Some(pos) = self
-                .window_compositor_updates
-                .iter()
-                .position(|update| update.window_id == window_id)
-            {
-                pos
-            } else {
-                self.window_compositor_updates
-                    .push(WindowCompositorUpdate::new(window_id));
-                self.window_compositor_updates.len() - 1
-            };
-
-            // Update the scale factor right away.
-            window.lock().unwrap().set_scale_factor(scale_factor);
-            self.window_compositor_updates[pos].scale_changed = true;
+            match self.scale_coalescing.as_mut() {
+                Some(coalescing) => {
+                    coalescing.push(window_id, scale_factor, Instant::now());
+                    self.schedule_settled_scale_factors();
+                }
+                None => self.apply_scale_factor(window_id, scale_factor),
+            }
         } else if let Some(pointer) = self.pointer_surfaces.get(&surface.id()) {
             // Get the window, where the pointer resides right now.
             let focused_window = match pointer.pointer().winit_data().focused_window() {
@@ -239,6 +325,87 @@ impl WinitState {
         }
     }
 
+    /// Update the scale factor of the window right away, queueing the `ScaleFactorChanged`.
+    fn apply_scale_factor(&mut self, window_id: WindowId, scale_factor: f64) {
+        let window = match self.windows.get_mut().get(&window_id) {
+            Some(window) => window,
+            None => return,
+        };
+
+        let pos = if let Some(pos) = self
+            .window_compositor_updates
+            .iter()
+            .position(|update| update.window_id == window_id)
+        {
+            pos
+        } else {
+            self.window_compositor_updates
+                .push(WindowCompositorUpdate::new(window_id));
+            self.window_compositor_updates.len() - 1
+        };
+
+        window.lock().unwrap().set_scale_factor(scale_factor);
+        self.window_compositor_updates[pos].scale_changed = true;
+    }
+
+    /// Set the timer to apply the scale factors once they settle.
+    fn schedule_settled_scale_factors(&mut self) {
+        if let Some(token) = self.scale_coalescing_timer.take() {
+            self.loop_handle.remove(token);
+        }
+
+        let deadline = match self
+            .scale_coalescing
+            .as_ref()
+            .and_then(ScaleCoalescing::next_deadline)
+        {
+            Some(deadline) => deadline,
+            None => return,
+        };
+
+        self.scale_coalescing_timer = self
+            .loop_handle
+            .insert_source(Timer::from_deadline(deadline), |_, _, state| {
+                let coalescing = state.scale_coalescing.as_mut().unwrap();
+                for (window_id, scale_factor) in coalescing.settled(Instant::now()) {
+                    // The scale factor could come back to the one the window already has.
+                    let changed = state
+                        .windows
+                        .get_mut()
+                        .get(&window_id)
+                        .is_some_and(|window| {
+                            window.lock().unwrap().scale_factor() != scale_factor
+                        });
+                    if changed {
+                        state.apply_scale_factor(window_id, scale_factor);
+                        state.dispatched_events = true;
+                    }
+                }
+
+                match state.scale_coalescing.as_ref().unwrap().next_deadline() {
+                    Some(deadline) => TimeoutAction::ToInstant(deadline),
+                    None => {
+                        state.scale_coalescing_timer = None;
+                        TimeoutAction::Drop
+                    }
+                }
+            })
+            .ok();
+    }
+
+    /// Lock the window of the `surface`, if it's a registered window.
+    ///
+    /// The events could reference a surface which isn't a window, or even a nil one, like
+    /// `leave` for a destroyed surface, so they're all looked up here.
+    pub fn window_for_surface<'a>(
+        windows: &'a AHashMap<WindowId, Arc<Mutex<WindowState>>>,
+        surface: &WlSurface,
+    ) -> Option<MutexGuard<'a, WindowState>> {
+        windows
+            .get(&super::make_wid(surface))
+            .map(|window| window.lock().unwrap())
+    }
+
     pub fn queue_close(updates: &mut Vec<WindowCompositorUpdate>, window_id: WindowId) {
         let pos = if let Some(pos) = updates
             .iter()
@@ -252,6 +419,106 @@ impl WinitState {
 
         updates[pos].close_window = true;
     }
+
+    /// Start animating the cursor of the window when it's animated, stopping the previous
+    /// animation.
+    pub fn update_cursor_animation(&mut self, window_id: WindowId) {
+        if let Some(token) = self.cursor_animations.remove(&window_id) {
+            self.loop_handle.remove(token);
+        }
+
+        let themes = match self.cursor_themes.as_mut() {
+            Some(themes) => themes,
+            None => return,
+        };
+
+        let animation = match self.windows.get_mut().get(&window_id) {
+            Some(window) => {
+                let window = window.lock().unwrap();
+                window
+                    .animated_cursor()
+                    .and_then(|icon| Some((icon, window.animate_cursor(themes, icon, 0)?)))
+            }
+            None => None,
+        };
+
+        let (icon, delay) = match animation {
+            Some(animation) => animation,
+            None => return,
+        };
+
+        let start = Instant::now();
+        let token =
+            self.loop_handle
+                .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                    let themes = state.cursor_themes.as_mut().unwrap();
+                    let next_frame = state.windows.get_mut().get(&window_id).and_then(|window| {
+                        let window = window.lock().unwrap();
+                        // Stop once the cursor changed.
+                        if window.animated_cursor() != Some(icon) {
+                            return None;
+                        }
+
+                        let millis = start.elapsed().as_millis() as u32;
+                        window.animate_cursor(themes, icon, millis)
+                    });
+
+                    match next_frame {
+                        Some(delay) => TimeoutAction::ToDuration(delay),
+                        None => {
+                            state.cursor_animations.remove(&window_id);
+                            TimeoutAction::Drop
+                        }
+                    }
+                });
+
+        match token {
+            Ok(token) => {
+                self.cursor_animations.insert(window_id, token);
+            }
+            Err(err) => log::warn!("Failed to animate the cursor: {err}"),
+        }
+    }
+
+    /// Wake up the loop after `delay` to send the redraw deferred by the frame rate limit.
+    pub fn defer_redraw(&mut self, window_id: WindowId, delay: Duration) {
+        if self.deferred_redraws.contains_key(&window_id) {
+            return;
+        }
+
+        let token =
+            self.loop_handle
+                .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                    state.deferred_redraws.remove(&window_id);
+                    state.dispatched_events = true;
+                    TimeoutAction::Drop
+                });
+
+        match token {
+            Ok(token) => {
+                self.deferred_redraws.insert(window_id, token);
+            }
+            Err(err) => log::warn!("Failed to defer the redraw: {err}"),
+        }
+    }
+
+    /// Propagate the color scheme preferred by the system to the windows.
+    pub fn system_theme_changed(&mut self, theme: Option<Theme>) {
+        if self.system_theme == theme {
+            return;
+        }
+
+        self.system_theme = theme;
+        for (window_id, window) in self.windows.get_mut() {
+            let changed = window.lock().unwrap().set_system_theme(theme);
+            if let Some(theme) = theme.filter(|_| changed) {
+                self.events_sink
+                    .push_window_event(WindowEvent::ThemeChanged(theme), *window_id);
+            }
+        }
+
+        self.dispatched_events = true;
+    }
 }
 
 impl ShmHandler for WinitState {
@@ -289,23 +556,23 @@ impl WindowHandler for WinitState {
         };
 
         // Populate the configure to the window.
-        self.window_compositor_updates[pos].resized |= self
+        let mut window = self
             .windows
             .get_mut()
             .get_mut(&window_id)
             .expect("got configure for dead window.")
             .lock()
-            .unwrap()
-            .configure(configure, &self.shm, &self.subcompositor_state);
-
-        // NOTE: configure demands wl_surface::commit, however winit doesn't commit on behalf of the
-        // users, since it can break a lot of things, thus it'll ask users to redraw instead.
-        self.window_requests
-            .get_mut()
-            .get(&window_id)
-            .unwrap()
-            .redraw_requested
-            .store(true, Ordering::Relaxed);
+            .unwrap();
+        let resized = window.configure(configure, &self.shm, &self.subcompositor_state);
+        self.window_compositor_updates[pos].resized |= window.debounce_resize(resized);
+        let window_requests = self.window_requests.get_mut().get(&window_id).unwrap();
+        let occluded = window.configure_presentation(window_requests);
+        drop(window);
+
+        if let Some(occluded) = occluded {
+            self.events_sink
+                .push_window_event(WindowEvent::Occluded(occluded), window_id);
+        }
 
         // Manually mark that we've got an event, since configure may not generate a resize.
         self.dispatched_events = true;
@@ -364,7 +631,7 @@ impl CompositorHandler for WinitState {
         self.scale_factor_changed(surface, scale_factor as f64, true)
     }
 
-    fn frame(&mut self, _: &Connection, _: &QueueHandle<Self>, surface: &WlSurface, _: u32) {
+    fn frame(&mut self, _: &Connection, _: &QueueHandle<Self>, surface: &WlSurface, time: u32) {
         let window_id = super::make_wid(surface);
         let window = match self.windows.get_mut().get(&window_id) {
             Some(window) => window,
@@ -383,7 +650,7 @@ impl CompositorHandler for WinitState {
             self.dispatched_events = true;
         }
 
-        window.lock().unwrap().frame_callback_received();
+        window.lock().unwrap().frame_callback_received(time);
     }
 }
 
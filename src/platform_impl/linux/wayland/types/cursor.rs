@@ -1,8 +1,16 @@
+use std::collections::hash_map::Entry;
+use std::env;
+use std::iter;
+
+use ahash::AHashMap;
 use cursor_icon::CursorIcon;
 
-use sctk::reexports::client::protocol::wl_shm::Format;
+use sctk::reexports::client::protocol::wl_shm::{Format, WlShm};
+use sctk::reexports::client::Connection;
 use sctk::shm::slot::{Buffer, SlotPool};
 
+use wayland_cursor::{Cursor, CursorTheme};
+
 use crate::cursor::CursorImage;
 
 #[derive(Debug)]
@@ -27,15 +35,32 @@ pub struct CustomCursor {
 }
 
 impl CustomCursor {
-    pub(crate) fn new(pool: &mut SlotPool, image: &CursorImage) -> Self {
-        let (buffer, canvas) = pool
-            .create_buffer(
-                image.width as i32,
-                image.height as i32,
-                4 * (image.width as i32),
-                Format::Argb8888,
-            )
-            .unwrap();
+    /// Create the cursor buffer, `None` is returned when it couldn't be created, which is logged.
+    pub(crate) fn new(
+        pool: &mut SlotPool,
+        image: &CursorImage,
+        formats: &[Format],
+    ) -> Option<Self> {
+        let format = match select_format(formats) {
+            Some(format) => format,
+            None => {
+                log::warn!("No compatible shm format for the custom cursor in {formats:?}");
+                return None;
+            }
+        };
+
+        let (buffer, canvas) = match pool.create_buffer(
+            image.width as i32,
+            image.height as i32,
+            4 * (image.width as i32),
+            format,
+        ) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                log::warn!("Failed to create the custom cursor buffer: {err}");
+                return None;
+            }
+        };
 
         for (canvas_chunk, rgba) in canvas.chunks_exact_mut(4).zip(image.rgba.chunks_exact(4)) {
             // Alpha in buffer is premultiplied.
@@ -48,12 +73,138 @@ impl CustomCursor {
             *array = color.to_le_bytes();
         }
 
-        CustomCursor {
+        Some(CustomCursor {
             buffer,
             w: image.width as i32,
             h: image.height as i32,
             hotspot_x: image.hotspot_x as i32,
             hotspot_y: image.hotspot_y as i32,
+        })
+    }
+}
+
+/// The system cursor theme, loaded by winit to animate the themed cursors.
+///
+/// The compositor takes care of the animation when the cursor shape protocol is available, so
+/// this is only used as a fallback.
+pub struct CursorThemes {
+    name: String,
+    size: u32,
+    shm: WlShm,
+    /// The theme for every scale the cursor was requested with.
+    themes: AHashMap<u32, CursorTheme>,
+}
+
+impl CursorThemes {
+    pub fn new(shm: WlShm) -> Self {
+        // Pick the same theme as the one used for the static cursors.
+        let name = env::var("XCURSOR_THEME").unwrap_or_else(|_| "default".into());
+        let size = env::var("XCURSOR_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(24);
+
+        Self {
+            name,
+            size,
+            shm,
+            themes: Default::default(),
         }
     }
+
+    /// Get the cursor for the given icon, loading the theme for the `scale` when needed.
+    pub fn get_cursor(
+        &mut self,
+        connection: &Connection,
+        icon: CursorIcon,
+        scale: u32,
+    ) -> Option<&Cursor> {
+        let theme = match self.themes.entry(scale) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let theme = CursorTheme::load_from_name(
+                    connection,
+                    self.shm.clone(),
+                    &self.name,
+                    self.size * scale,
+                )
+                .map_err(|err| log::warn!("Failed to load the cursor theme: {err}"))
+                .ok()?;
+                entry.insert(theme)
+            }
+        };
+
+        let name = iter::once(icon.name())
+            .chain(icon.alt_names().iter().copied())
+            .find(|name| theme.get_cursor(name).is_some())?;
+        theme.get_cursor(name)
+    }
+}
+
+/// Find the frame to show after `millis` of the animation with the given frame `delays`, and how
+/// long it remains shown, in milliseconds.
+///
+/// The animation loops, `None` is returned when there's nothing to animate.
+pub fn animation_frame(delays: &[u32], millis: u32) -> Option<(usize, u32)> {
+    let total: u32 = delays.iter().sum();
+    if delays.len() < 2 || total == 0 {
+        return None;
+    }
+
+    let mut millis = millis % total;
+    for (index, &delay) in delays.iter().enumerate() {
+        if millis < delay {
+            return Some((index, delay - millis));
+        }
+        millis -= delay;
+    }
+
+    unreachable!("the time is wrapped to the animation duration")
+}
+
+/// Pick the format for the cursor buffer among the ones supported by the compositor.
+///
+/// `Argb8888` is preferred, falling back to `Xrgb8888`, which shares the same layout but loses the
+/// transparency.
+fn select_format(formats: &[Format]) -> Option<Format> {
+    // The formats may not be advertised yet, but `Argb8888` is mandatory.
+    if formats.is_empty() || formats.contains(&Format::Argb8888) {
+        Some(Format::Argb8888)
+    } else if formats.contains(&Format::Xrgb8888) {
+        log::info!("Argb8888 shm format is unavailable, custom cursors won't be transparent");
+        Some(Format::Xrgb8888)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shm_format_fallback() {
+        assert_eq!(select_format(&[]), Some(Format::Argb8888));
+        assert_eq!(
+            select_format(&[Format::Xrgb8888, Format::Argb8888]),
+            Some(Format::Argb8888)
+        );
+        assert_eq!(select_format(&[Format::Xrgb8888]), Some(Format::Xrgb8888));
+        assert_eq!(select_format(&[Format::Rgb565]), None);
+    }
+
+    #[test]
+    fn cursor_animation_frames() {
+        assert_eq!(animation_frame(&[], 0), None);
+        assert_eq!(animation_frame(&[50], 10), None);
+        assert_eq!(animation_frame(&[0, 0], 10), None);
+
+        let delays = [50, 20, 30];
+        assert_eq!(animation_frame(&delays, 0), Some((0, 50)));
+        assert_eq!(animation_frame(&delays, 49), Some((0, 1)));
+        assert_eq!(animation_frame(&delays, 50), Some((1, 20)));
+        assert_eq!(animation_frame(&delays, 75), Some((2, 25)));
+        // The animation loops.
+        assert_eq!(animation_frame(&delays, 110), Some((0, 40)));
+    }
 }
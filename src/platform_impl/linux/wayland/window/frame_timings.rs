@@ -0,0 +1,146 @@
+//! The frame timings of a window, derived from its frame callbacks.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::window::FrameStats;
+
+/// The number of recent frame intervals the refresh interval is estimated from.
+const INTERVALS: usize = 32;
+
+/// How soon after the last frame callback the next frame should be presented to be continuous,
+/// before the refresh interval is known.
+const INITIAL_CONTINUITY: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default)]
+pub struct FrameTimings {
+    /// The time the last frame was presented.
+    last_present: Option<Instant>,
+
+    /// Whether a frame was presented since the last frame callback.
+    presented: bool,
+
+    /// Whether the frame presented since the last frame callback followed it right away.
+    continuous: bool,
+
+    /// The compositor timestamp, in milliseconds, and the receive time of the last frame callback.
+    last_callback: Option<(u32, Instant)>,
+
+    /// The recent intervals between the frame callbacks of continuous frames, in milliseconds.
+    intervals: VecDeque<u32>,
+
+    /// The number of frames missed while presenting continuously.
+    missed_frames: u64,
+}
+
+impl FrameTimings {
+    /// A frame is about to be presented.
+    pub fn presented(&mut self, now: Instant) {
+        self.last_present = Some(now);
+
+        // Only the first frame after the callback tells when the user started drawing.
+        if self.presented {
+            return;
+        }
+        self.presented = true;
+
+        let continuity = self.refresh_interval().unwrap_or(INITIAL_CONTINUITY);
+        self.continuous = self
+            .last_callback
+            .is_some_and(|(_, received)| now.saturating_duration_since(received) < continuity);
+    }
+
+    /// The frame callback with the given compositor `timestamp` was received.
+    pub fn frame_callback(&mut self, timestamp: u32, now: Instant) {
+        if let (true, Some((last_timestamp, _))) = (self.continuous, self.last_callback) {
+            // Callbacks fired at once don't tell anything about the refresh.
+            let interval = timestamp.wrapping_sub(last_timestamp);
+            if interval != 0 {
+                if let Some(refresh) = self.refresh_interval_ms() {
+                    let frames = (interval + refresh / 2) / refresh;
+                    self.missed_frames += frames.saturating_sub(1) as u64;
+                }
+
+                if self.intervals.len() == INTERVALS {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(interval);
+            }
+        }
+
+        self.last_callback = Some((timestamp, now));
+        self.presented = false;
+        self.continuous = false;
+    }
+
+    pub fn stats(&self, now: Instant) -> FrameStats {
+        FrameStats {
+            since_last_present: self
+                .last_present
+                .map(|present| now.saturating_duration_since(present)),
+            refresh_interval: self.refresh_interval(),
+            missed_frames: self.missed_frames,
+        }
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval_ms()
+            .map(|refresh| Duration::from_millis(refresh as u64))
+    }
+
+    /// The frame callbacks can't be sent faster than the refresh, so the shortest interval is the
+    /// closest to it.
+    fn refresh_interval_ms(&self) -> Option<u32> {
+        self.intervals.iter().copied().min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Present a frame right after every callback, the callbacks coming at the given timestamps.
+    fn present_continuously(timings: &mut FrameTimings, start: Instant, timestamps: &[u32]) {
+        for &timestamp in timestamps {
+            let received = start + Duration::from_millis(timestamp as u64);
+            timings.frame_callback(timestamp, received);
+            timings.presented(received + Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn counts_missed_frames() {
+        let start = Instant::now();
+        let mut timings = FrameTimings::default();
+        assert_eq!(timings.stats(start), FrameStats::default());
+
+        // 60Hz, with a frame missed after the fourth one.
+        present_continuously(&mut timings, start, &[0, 16, 33, 50, 83, 100, 116]);
+
+        let now = start + Duration::from_millis(120);
+        let stats = timings.stats(now);
+        assert_eq!(stats.refresh_interval, Some(Duration::from_millis(16)));
+        assert_eq!(stats.missed_frames, 1);
+        assert_eq!(stats.since_last_present, Some(Duration::from_millis(3)));
+    }
+
+    #[test]
+    fn idle_isnt_missing_frames() {
+        let start = Instant::now();
+        let mut timings = FrameTimings::default();
+
+        present_continuously(&mut timings, start, &[0, 16, 33]);
+
+        // Nothing to draw for a while, then a single frame.
+        let received = start + Duration::from_millis(50);
+        timings.frame_callback(50, received);
+        timings.presented(received + Duration::from_millis(500));
+        timings.frame_callback(566, received + Duration::from_millis(516));
+
+        present_continuously(&mut timings, start, &[583, 600]);
+
+        let stats = timings.stats(start + Duration::from_millis(600));
+        assert_eq!(stats.refresh_interval, Some(Duration::from_millis(16)));
+        assert_eq!(stats.missed_frames, 0);
+    }
+}
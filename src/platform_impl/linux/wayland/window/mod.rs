@@ -1,31 +1,36 @@
 //! The Wayland window.
 
+use std::io::ErrorKind;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use sctk::reexports::client::backend::WaylandError as WaylandClientError;
 use sctk::reexports::client::protocol::wl_display::WlDisplay;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::Proxy;
-use sctk::reexports::client::QueueHandle;
+use sctk::reexports::client::{DispatchError, EventQueue, QueueHandle};
 
-use sctk::compositor::{CompositorState, Region, SurfaceData};
+use sctk::compositor::SurfaceData;
 use sctk::reexports::protocols::xdg::activation::v1::client::xdg_activation_v1::XdgActivationV1;
 use sctk::shell::xdg::window::Window as SctkWindow;
 use sctk::shell::xdg::window::WindowDecorations;
 use sctk::shell::WaylandSurface;
 
 use log::warn;
+use rustix::event::{PollFd, PollFlags};
 
 use crate::dpi::{LogicalSize, PhysicalPosition, PhysicalSize, Position, Size};
 use crate::error::{ExternalError, NotSupportedError, OsError as RootOsError};
 use crate::event::{Ime, WindowEvent};
 use crate::event_loop::AsyncRequestSerial;
+use crate::platform::wayland::DecorationMode;
 use crate::platform_impl::{
     Fullscreen, MonitorHandle as PlatformMonitorHandle, OsError, PlatformIcon,
 };
 use crate::window::{
-    Cursor, CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType,
-    WindowAttributes, WindowButtons, WindowLevel,
+    Cursor, CursorGrabMode, FrameStats, ImeContentHints, ImePurpose, ResizeDirection, Theme,
+    UserAttentionType, WindowAttributes, WindowButtons, WindowLevel,
 };
 
 use super::event_loop::sink::EventSink;
@@ -34,8 +39,12 @@ use super::state::WinitState;
 use super::types::xdg_activation::XdgActivationTokenData;
 use super::{ActiveEventLoop, WaylandError, WindowId};
 
+mod frame_timings;
 pub(crate) mod state;
 
+/// How long the window creation waits for the compositor to send the initial configure.
+const INITIAL_CONFIGURE_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub use state::WindowState;
 
 /// The Wayland window.
@@ -49,9 +58,6 @@ pub struct Window {
     /// The state of the window.
     window_state: Arc<Mutex<WindowState>>,
 
-    /// Compositor to handle WlRegion stuff.
-    compositor: Arc<CompositorState>,
-
     /// The wayland display used solely for raw window handle.
     #[allow(dead_code)]
     display: WlDisplay,
@@ -86,10 +92,14 @@ impl Window {
         let queue_handle = event_loop_window_target.queue_handle.clone();
         let mut state = event_loop_window_target.state.borrow_mut();
 
+        #[cfg(feature = "wayland-portal")]
+        if let Some(watcher) = state.color_scheme_watcher.as_mut() {
+            watcher.start();
+        }
+
         let monitors = state.monitors.clone();
 
         let surface = state.compositor_state.create_surface(&queue_handle);
-        let compositor = state.compositor_state.clone();
         let xdg_activation = state
             .xdg_activation
             .as_ref()
@@ -171,10 +181,15 @@ impl Window {
             Cursor::Custom(cursor) => window_state.set_custom_cursor(cursor),
         }
 
-        // Activate the window when the token is passed.
+        // Activate the window when the token is passed, the token the application was launched
+        // with is used by the first window, unless it was given its own.
+        let startup_token = state.startup_activation_token.take();
         if let (Some(xdg_activation), Some(token)) = (
             xdg_activation.as_ref(),
-            attributes.platform_specific.activation_token,
+            attributes
+                .platform_specific
+                .activation_token
+                .or(startup_token),
         ) {
             xdg_activation.activate(token._token, &surface);
         }
@@ -189,6 +204,7 @@ impl Window {
             .windows
             .get_mut()
             .insert(window_id, window_state.clone());
+        state.text_input_window_registered(window_id);
 
         let window_requests = WindowRequests {
             redraw_requested: AtomicBool::new(true),
@@ -213,9 +229,17 @@ impl Window {
             ))))
         })?;
 
-        // XXX Wait for the initial configure to arrive.
+        // XXX Wait for the initial configure to arrive, but don't block forever on compositors
+        // which are slow to send it. The window keeps the requested size until it arrives.
+        let deadline = Instant::now() + INITIAL_CONFIGURE_TIMEOUT;
         while !window_state.lock().unwrap().is_configured() {
-            event_queue.blocking_dispatch(&mut state).map_err(|error| {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                warn!("Initial configure didn't arrive in {INITIAL_CONFIGURE_TIMEOUT:?}");
+                break;
+            }
+
+            dispatch_with_timeout(event_queue, &mut state, timeout).map_err(|error| {
                 os_error!(OsError::WaylandError(Arc::new(WaylandError::Dispatch(
                     error
                 ))))
@@ -231,7 +255,6 @@ impl Window {
             display,
             monitors,
             window_id,
-            compositor,
             window_state,
             queue_handle,
             xdg_activation,
@@ -262,7 +285,7 @@ impl Window {
 
     #[inline]
     pub fn is_visible(&self) -> Option<bool> {
-        None
+        self.window_state.lock().unwrap().is_visible(Instant::now())
     }
 
     #[inline]
@@ -305,7 +328,19 @@ impl Window {
 
     #[inline]
     pub fn pre_present_notify(&self) {
-        self.window_state.lock().unwrap().request_frame_callback();
+        self.window_state.lock().unwrap().pre_present_notify();
+    }
+
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.window_state.lock().unwrap().frame_stats()
+    }
+
+    #[inline]
+    pub fn set_max_frame_rate(&self, rate: Option<u32>) {
+        self.window_state.lock().unwrap().set_max_frame_rate(rate);
+        // Send the redraw deferred by the old limit.
+        self.event_loop_awakener.ping();
     }
 
     #[inline]
@@ -374,8 +409,10 @@ impl Window {
 
     #[inline]
     pub fn is_minimized(&self) -> Option<bool> {
-        // XXX clients don't know whether they are minimized or not.
-        None
+        self.window_state
+            .lock()
+            .unwrap()
+            .is_minimized(Instant::now())
     }
 
     #[inline]
@@ -422,6 +459,20 @@ impl Window {
         self.window_state.lock().unwrap().scale_factor()
     }
 
+    #[inline]
+    pub fn integer_scale_factor(&self) -> i32 {
+        self.window
+            .wl_surface()
+            .data::<SurfaceData>()
+            .unwrap()
+            .scale_factor()
+    }
+
+    #[inline]
+    pub fn has_fractional_scale(&self) -> bool {
+        self.window_state.lock().unwrap().has_fractional_scale()
+    }
+
     #[inline]
     pub fn set_blur(&self, blur: bool) {
         self.window_state.lock().unwrap().set_blur(blur);
@@ -437,6 +488,12 @@ impl Window {
         self.window_state.lock().unwrap().is_decorated()
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        // Drag and drop isn't implemented, even when `wl_data_device_manager` is available.
+        false
+    }
+
     #[inline]
     pub fn set_window_level(&self, _level: WindowLevel) {}
 
@@ -451,7 +508,7 @@ impl Window {
             return;
         }
 
-        self.window.set_minimized();
+        self.window_state.lock().unwrap().set_minimized();
     }
 
     #[inline]
@@ -520,6 +577,9 @@ impl Window {
             Cursor::Icon(icon) => window_state.set_cursor(icon),
             Cursor::Custom(cursor) => window_state.set_custom_cursor(cursor),
         }
+
+        // Update the cursor animation.
+        self.event_loop_awakener.ping();
     }
 
     #[inline]
@@ -528,6 +588,9 @@ impl Window {
             .lock()
             .unwrap()
             .set_cursor_visible(visible);
+
+        // Update the cursor animation.
+        self.event_loop_awakener.ping();
     }
 
     pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
@@ -589,6 +652,15 @@ impl Window {
             .map(|_| self.request_redraw())
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        let window_state = self.window_state.lock().unwrap();
+        let scale_factor = window_state.scale_factor();
+        window_state
+            .cursor_position()
+            .map(|position| position.to_physical(scale_factor))
+    }
+
     #[inline]
     pub fn drag_window(&self) -> Result<(), ExternalError> {
         self.window_state.lock().unwrap().drag_window()
@@ -596,30 +668,37 @@ impl Window {
 
     #[inline]
     pub fn set_cursor_hittest(&self, hittest: bool) -> Result<(), ExternalError> {
-        let surface = self.window.wl_surface();
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_cursor_hittest(hittest)
+    }
 
-        if hittest {
-            surface.set_input_region(None);
-            Ok(())
-        } else {
-            let region = Region::new(&*self.compositor).map_err(|_| {
-                ExternalError::Os(os_error!(OsError::Misc("failed to set input region.")))
-            })?;
-            region.add(0, 0, 0, 0);
-            surface.set_input_region(Some(region.wl_region()));
-            Ok(())
-        }
+    #[inline]
+    pub fn set_input_region(
+        &self,
+        region: Option<Vec<(Position, Size)>>,
+    ) -> Result<(), ExternalError> {
+        self.window_state.lock().unwrap().set_input_region(region)
     }
 
     #[inline]
     pub fn set_ime_cursor_area(&self, position: Position, size: Size) {
-        let window_state = self.window_state.lock().unwrap();
-        if window_state.ime_allowed() {
-            let scale_factor = window_state.scale_factor();
-            let position = position.to_logical(scale_factor);
-            let size = size.to_logical(scale_factor);
-            window_state.set_ime_cursor_area(position, size);
-        }
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_cursor_area(position, size);
+        self.event_loop_awakener.ping();
+    }
+
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.window_state.lock().unwrap().ime_cursor_area()
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        self.window_state.lock().unwrap().ime_supported()
     }
 
     #[inline]
@@ -627,7 +706,24 @@ impl Window {
         let mut window_state = self.window_state.lock().unwrap();
 
         if window_state.ime_allowed() != allowed && window_state.set_ime_allowed(allowed) {
-            let event = WindowEvent::Ime(if allowed { Ime::Enabled } else { Ime::Disabled });
+            let event = if allowed { Ime::Enabled } else { Ime::Disabled };
+            if let Some(event) = window_state.filter_ime_enablement(event) {
+                self.window_events_sink
+                    .lock()
+                    .unwrap()
+                    .push_window_event(WindowEvent::Ime(event), self.window_id);
+            }
+            self.event_loop_awakener.ping();
+        }
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, bypass: bool) {
+        let mut window_state = self.window_state.lock().unwrap();
+
+        // The preedit is discarded with the disabled text inputs.
+        if window_state.set_ime_bypass(bypass) {
+            let event = WindowEvent::Ime(Ime::Preedit(String::new(), None));
             self.window_events_sink
                 .lock()
                 .unwrap()
@@ -636,9 +732,75 @@ impl Window {
         }
     }
 
+    #[inline]
+    pub fn set_ime_preedit_clear_on_done(&self, clear: bool) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_preedit_clear_on_done(clear);
+    }
+
+    #[inline]
+    pub fn set_ime_batched_updates(&self, batched: bool) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_batched_updates(batched);
+    }
+
+    #[inline]
+    pub fn set_ime_key_presses_while_composing(&self, deliver: bool) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_key_presses_while_composing(deliver);
+    }
+
+    #[inline]
+    pub fn set_debounce_interactive_resize(&self, debounce: bool) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_debounce_interactive_resize(debounce);
+    }
+
+    #[inline]
+    pub fn decoration_mode(&self) -> DecorationMode {
+        self.window_state.lock().unwrap().decoration_mode()
+    }
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, text: String, cursor: usize, anchor: usize) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_surrounding_text(text, cursor, anchor);
+        self.event_loop_awakener.ping();
+    }
+
     #[inline]
     pub fn set_ime_purpose(&self, purpose: ImePurpose) {
         self.window_state.lock().unwrap().set_ime_purpose(purpose);
+        self.event_loop_awakener.ping();
+    }
+
+    #[inline]
+    pub fn set_ime_content_hints(&self, hints: ImeContentHints) {
+        self.window_state
+            .lock()
+            .unwrap()
+            .set_ime_content_hints(hints);
+        self.event_loop_awakener.ping();
+    }
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        self.window_state.lock().unwrap().ime_allowed()
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        self.window_state.lock().unwrap().ime_purpose()
     }
 
     #[inline]
@@ -777,3 +939,39 @@ impl TryFrom<&str> for Theme {
         }
     }
 }
+
+/// Like [`EventQueue::blocking_dispatch`], but waits for the events at most for `timeout`.
+fn dispatch_with_timeout(
+    event_queue: &mut EventQueue<WinitState>,
+    state: &mut WinitState,
+    timeout: Duration,
+) -> Result<usize, DispatchError> {
+    let dispatched = event_queue.dispatch_pending(state)?;
+    if dispatched > 0 {
+        return Ok(dispatched);
+    }
+
+    event_queue.flush()?;
+
+    if let Some(guard) = event_queue.prepare_read() {
+        let fd = guard.connection_fd();
+        let mut fds = [PollFd::new(&fd, PollFlags::IN | PollFlags::ERR)];
+        let timeout = timeout.as_millis().try_into().unwrap_or(i32::MAX);
+        let ready = match rustix::event::poll(&mut fds, timeout) {
+            Ok(ready) => ready > 0,
+            // Let the caller retry.
+            Err(rustix::io::Errno::INTR) => false,
+            Err(err) => return Err(WaylandClientError::Io(err.into()).into()),
+        };
+
+        if ready {
+            match guard.read() {
+                Ok(_) => (),
+                Err(WaylandClientError::Io(err)) if err.kind() == ErrorKind::WouldBlock => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    event_queue.dispatch_pending(state)
+}
@@ -1,15 +1,18 @@
 //! The state of the window, which is shared with the event-loop.
 
+use std::mem;
 use std::num::NonZeroU32;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex, Weak};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ahash::HashSet;
 use log::{info, warn};
 
 use sctk::reexports::client::backend::ObjectId;
+use sctk::reexports::client::protocol::wl_buffer::WlBuffer;
 use sctk::reexports::client::protocol::wl_seat::WlSeat;
-use sctk::reexports::client::protocol::wl_shm::WlShm;
+use sctk::reexports::client::protocol::wl_shm::{Format, WlShm};
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 use sctk::reexports::csd_frame::{
@@ -31,19 +34,29 @@ use sctk::subcompositor::SubcompositorState;
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur::OrgKdeKwinBlur;
 
 use crate::cursor::CustomCursor as RootCustomCursor;
-use crate::dpi::{LogicalPosition, LogicalSize, PhysicalSize, Size};
+use crate::dpi::{LogicalPosition, LogicalSize, PhysicalSize, Position, Size};
 use crate::error::{ExternalError, NotSupportedError};
+use crate::event::Ime;
+use crate::platform::wayland::DecorationMode as WaylandDecorationMode;
 use crate::platform_impl::wayland::logical_to_physical_rounded;
-use crate::platform_impl::wayland::types::cursor::{CustomCursor, SelectedCursor};
+use crate::platform_impl::wayland::types::cursor::{
+    animation_frame, CursorThemes, CustomCursor, SelectedCursor,
+};
 use crate::platform_impl::wayland::types::kwin_blur::KWinBlurManager;
 use crate::platform_impl::{PlatformCustomCursor, WindowId};
-use crate::window::{CursorGrabMode, CursorIcon, ImePurpose, ResizeDirection, Theme};
+use crate::window::{
+    CursorGrabMode, CursorIcon, FrameStats, ImeContentHints, ImePurpose, ResizeDirection, Theme,
+};
 
 use crate::platform_impl::wayland::seat::{
-    PointerConstraintsState, WinitPointerData, WinitPointerDataExt, ZwpTextInputV3Ext,
+    commit_state, ImeEnablement, PendingTextInputState, PointerConstraintsState, SurroundingText,
+    WinitPointerData, WinitPointerDataExt,
 };
 use crate::platform_impl::wayland::state::{WindowCompositorUpdate, WinitState};
 
+use super::frame_timings::FrameTimings;
+use super::WindowRequests;
+
 #[cfg(feature = "sctk-adwaita")]
 pub type WinitFrame = sctk_adwaita::AdwaitaFrame<WinitState>;
 #[cfg(not(feature = "sctk-adwaita"))]
@@ -52,13 +65,16 @@ pub type WinitFrame = sctk::shell::xdg::fallback_frame::FallbackFrame<WinitState
 // Minimum window inner size.
 const MIN_WINDOW_SIZE: LogicalSize<u32> = LogicalSize::new(2, 1);
 
+/// How long the frame callback of a window is pending before the window is assumed to stay hidden.
+const HIDDEN_FRAME_CALLBACK_DELAY: Duration = Duration::from_secs(1);
+
 /// The state of the window which is being updated from the [`WinitState`].
 pub struct WindowState {
     /// The connection to Wayland server.
     pub connection: Connection,
 
-    /// The window frame, which is created from the configure request.
-    frame: Option<WinitFrame>,
+    /// The client side decorations, which are created from the configure request.
+    decorations: Decorations<WinitFrame>,
 
     /// The `Shm` to set cursor.
     pub shm: WlShm,
@@ -66,17 +82,27 @@ pub struct WindowState {
     // A shared pool where to allocate custom cursors.
     custom_cursor_pool: Arc<Mutex<SlotPool>>,
 
+    /// The formats supported by the `Shm`.
+    shm_formats: Vec<Format>,
+
     /// The last received configure.
     pub last_configure: Option<WindowConfigure>,
 
     /// The pointers observed on the window.
     pub pointers: Vec<Weak<ThemedPointer<WinitPointerData>>>,
 
+    /// The last position of the pointer on the main surface.
+    cursor_position: Option<LogicalPosition<f64>>,
+
     selected_cursor: SelectedCursor,
 
     /// Whether the cursor is visible.
     pub cursor_visible: bool,
 
+    /// Whether the cursor or the pointers on the window changed since the last time the cursor
+    /// animation was updated.
+    cursor_changed: bool,
+
     /// Pointer constraints to lock/confine pointer.
     pub pointer_constraints: Option<Arc<PointerConstraintsState>>,
 
@@ -86,6 +112,9 @@ pub struct WindowState {
     /// Theme variant.
     theme: Option<Theme>,
 
+    /// The color scheme preferred by the system, used when there's no theme variant.
+    system_theme: Option<Theme>,
+
     /// The current window title.
     title: String,
 
@@ -103,6 +132,9 @@ pub struct WindowState {
     /// Whether the window is transparent.
     transparent: bool,
 
+    /// The region of the window accepting input.
+    input_region: InputRegion,
+
     /// The state of the compositor to create WlRegions.
     compositor: Arc<CompositorState>,
 
@@ -112,18 +144,45 @@ pub struct WindowState {
     /// Whether the IME input is allowed for that window.
     ime_allowed: bool,
 
+    /// Whether the IME is bypassed for a moment, with the text inputs disabled.
+    ime_bypass: bool,
+
+    /// Whether the preedit was discarded by the bypass since the last IME update.
+    ime_preedit_discarded: bool,
+
     /// The current IME purpose.
     ime_purpose: ImePurpose,
 
+    /// Whether to clear the preedit at the start of every IME update.
+    ime_preedit_clear_on_done: bool,
+
+    /// Whether to send the IME changes of every update in a single event.
+    ime_batched_updates: bool,
+
+    /// Whether the IME has a preedit shown to the user.
+    ime_composing: bool,
+
+    /// Whether to send the key presses while the IME composes.
+    ime_key_presses_while_composing: bool,
+
+    /// The IME cursor area, as it was last requested by the user.
+    ime_cursor_area: Option<(Position, Size)>,
+
+    /// The IME state which will be sent to the text inputs on the next flush.
+    pending_ime_state: PendingTextInputState,
+
+    /// The `Enabled` and `Disabled` sent to the user.
+    ime_enablement: ImeEnablement,
+
     /// The text inputs observed on the window.
     text_inputs: Vec<ZwpTextInputV3>,
 
+    /// Whether the compositor supports text input, so IME could ever be enabled.
+    text_input_supported: bool,
+
     /// The inner size of the window, as in without client side decorations.
     size: LogicalSize<u32>,
 
-    /// Whether the CSD fail to create, so we don't try to create them on each iteration.
-    csd_fails: bool,
-
     /// Whether we should decorate the frame.
     decorate: bool,
 
@@ -140,8 +199,26 @@ pub struct WindowState {
     /// configure.
     initial_size: Option<Size>,
 
-    /// The state of the frame callback.
-    frame_callback_state: FrameCallbackState,
+    /// The suspension and the frame callbacks, which decide when the redraws are sent.
+    presentation: Presentation,
+
+    /// The minimum interval between the redraws, set from the frame rate limit.
+    min_frame_interval: Option<Duration>,
+
+    /// The time the last redraw was sent to the user.
+    last_redraw: Option<Instant>,
+
+    /// The timings of the frames presented by the user.
+    frame_timings: FrameTimings,
+
+    /// Whether the window was minimized by us and wasn't activated since.
+    minimize_requested: bool,
+
+    /// Whether to hold the resizes until the interactive resize ends.
+    debounce_interactive_resize: bool,
+
+    /// Whether a resize was held during the interactive resize.
+    resize_held: bool,
 
     viewport: Option<WpViewport>,
     fractional_scale: Option<WpFractionalScaleV1>,
@@ -183,44 +260,64 @@ impl WindowState {
             blur_manager: winit_state.kwin_blur_manager.clone(),
             compositor,
             connection,
-            csd_fails: false,
             cursor_grab_mode: GrabState::new(),
             selected_cursor: Default::default(),
             cursor_visible: true,
+            cursor_changed: false,
             decorate: true,
             fractional_scale,
-            frame: None,
-            frame_callback_state: FrameCallbackState::None,
+            decorations: Decorations::new(),
+            presentation: Default::default(),
+            min_frame_interval: None,
+            last_redraw: None,
+            frame_timings: Default::default(),
+            minimize_requested: false,
+            debounce_interactive_resize: false,
+            resize_held: false,
             seat_focus: Default::default(),
             has_pending_move: None,
             ime_allowed: false,
+            ime_bypass: false,
+            ime_preedit_discarded: false,
             ime_purpose: ImePurpose::Normal,
+            ime_preedit_clear_on_done: true,
+            ime_batched_updates: false,
+            ime_composing: false,
+            ime_key_presses_while_composing: true,
+            ime_cursor_area: None,
+            pending_ime_state: Default::default(),
+            ime_enablement: Default::default(),
             last_configure: None,
             max_inner_size: None,
             min_inner_size: MIN_WINDOW_SIZE,
             pointer_constraints,
             pointers: Default::default(),
+            cursor_position: None,
             queue_handle: queue_handle.clone(),
             resizable: true,
             scale_factor: 1.,
             shm: winit_state.shm.wl_shm().clone(),
             custom_cursor_pool: winit_state.custom_cursor_pool.clone(),
+            shm_formats: winit_state.shm.formats().to_vec(),
             size: initial_size.to_logical(1.),
             stateless_size: initial_size.to_logical(1.),
             initial_size: Some(initial_size),
             text_inputs: Vec::new(),
+            text_input_supported: winit_state.text_input_state.is_some(),
             theme,
+            system_theme: winit_state.system_theme,
             title: String::default(),
             transparent: false,
+            input_region: InputRegion::new(),
             viewport,
             window,
         }
     }
 
     /// Apply closure on the given pointer.
-    fn apply_on_pointer<F: Fn(&ThemedPointer<WinitPointerData>, &WinitPointerData)>(
+    fn apply_on_pointer<F: FnMut(&ThemedPointer<WinitPointerData>, &WinitPointerData)>(
         &self,
-        callback: F,
+        mut callback: F,
     ) {
         self.pointers
             .iter()
@@ -231,33 +328,70 @@ impl WindowState {
             })
     }
 
-    /// Get the current state of the frame callback.
-    pub fn frame_callback_state(&self) -> FrameCallbackState {
-        self.frame_callback_state
+    /// The frame callback with the compositor timestamp `time` was received, but not yet sent to
+    /// the user.
+    pub fn frame_callback_received(&mut self, time: u32) {
+        self.frame_timings.frame_callback(time, Instant::now());
+        self.presentation.frame_callback_received();
     }
 
-    /// The frame callback was received, but not yet sent to the user.
-    pub fn frame_callback_received(&mut self) {
-        self.frame_callback_state = FrameCallbackState::Received;
+    /// Whether the redraw could be sent to the user.
+    pub fn can_redraw(&self) -> bool {
+        self.presentation.can_redraw()
     }
 
-    /// Reset the frame callbacks state.
-    pub fn frame_callback_reset(&mut self) {
-        self.frame_callback_state = FrameCallbackState::None;
+    /// Take the redraw requested in `requests` when it can be sent to the user.
+    pub fn take_redraw(&mut self, requests: &WindowRequests) -> bool {
+        self.presentation.take_redraw(requests)
+    }
+
+    /// Apply the suspension of the last configure to the redraws, asking for one in `requests`.
+    ///
+    /// Returns whether the window is now occluded, when it changed.
+    pub fn configure_presentation(&mut self, requests: &WindowRequests) -> Option<bool> {
+        let suspended = self.is_suspended();
+        self.presentation.configure(suspended, requests)
+    }
+
+    /// The user is about to present a frame.
+    pub fn pre_present_notify(&mut self) {
+        self.frame_timings.presented(Instant::now());
+        self.request_frame_callback();
+    }
+
+    /// The timings of the frames presented by the user.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_timings.stats(Instant::now())
     }
 
     /// Request a frame callback if we don't have one for this window in flight.
     pub fn request_frame_callback(&mut self) {
-        let surface = self.window.wl_surface();
-        match self.frame_callback_state {
-            FrameCallbackState::None | FrameCallbackState::Received => {
-                self.frame_callback_state = FrameCallbackState::Requested;
-                surface.frame(&self.queue_handle, surface.clone());
-            }
-            FrameCallbackState::Requested => (),
+        if self.presentation.request_frame_callback(Instant::now()) {
+            let surface = self.window.wl_surface();
+            surface.frame(&self.queue_handle, surface.clone());
         }
     }
 
+    /// Limit the redraws to `rate` per second, `None` or `0` removing the limit.
+    pub fn set_max_frame_rate(&mut self, rate: Option<u32>) {
+        self.min_frame_interval = rate
+            .filter(|rate| *rate != 0)
+            .map(|rate| Duration::from_secs(1) / rate);
+    }
+
+    /// The time left until the next redraw is allowed by the frame rate limit.
+    pub fn redraw_delay(&self, now: Instant) -> Option<Duration> {
+        let next_redraw = self.last_redraw? + self.min_frame_interval?;
+        next_redraw
+            .checked_duration_since(now)
+            .filter(|delay| !delay.is_zero())
+    }
+
+    /// The redraw was sent to the user.
+    pub fn redraw_dispatched(&mut self, now: Instant) {
+        self.last_redraw = Some(now);
+    }
+
     pub fn configure(
         &mut self,
         configure: WindowConfigure,
@@ -272,60 +406,57 @@ impl WindowState {
             self.stateless_size = self.size;
         }
 
-        if let Some(subcompositor) = subcompositor.as_ref().filter(|_| {
-            configure.decoration_mode == DecorationMode::Client
-                && self.frame.is_none()
-                && !self.csd_fails
-        }) {
-            match WinitFrame::new(
-                &self.window,
-                shm,
-                #[cfg(feature = "sctk-adwaita")]
-                self.compositor.clone(),
-                subcompositor.clone(),
-                self.queue_handle.clone(),
-                #[cfg(feature = "sctk-adwaita")]
-                into_sctk_adwaita_config(self.theme),
-            ) {
-                Ok(mut frame) => {
+        let create_frame = subcompositor.as_ref().map(|subcompositor| {
+            || {
+                WinitFrame::new(
+                    &self.window,
+                    shm,
+                    #[cfg(feature = "sctk-adwaita")]
+                    self.compositor.clone(),
+                    subcompositor.clone(),
+                    self.queue_handle.clone(),
+                    #[cfg(feature = "sctk-adwaita")]
+                    into_sctk_adwaita_config(self.theme),
+                )
+                .map(|mut frame| {
                     frame.set_title(&self.title);
                     frame.set_scaling_factor(self.scale_factor);
-                    // Hide the frame if we were asked to not decorate.
-                    frame.set_hidden(!self.decorate);
-                    self.frame = Some(frame);
-                }
-                Err(err) => {
-                    warn!("Failed to create client side decorations frame: {err}");
-                    self.csd_fails = true;
-                }
+                    frame
+                })
             }
-        } else if configure.decoration_mode == DecorationMode::Server {
-            // Drop the frame for server side decorations to save resources.
-            self.frame = None;
-        }
+        });
+        self.decorations
+            .configure(configure.decoration_mode, self.decorate, create_frame);
 
         let stateless = Self::is_stateless(&configure);
+        let fallback_size = if stateless {
+            self.stateless_size
+        } else {
+            self.size
+        };
 
-        let (mut new_size, constrain) = if let Some(frame) = self.frame.as_mut() {
+        let (mut new_size, constrain) = if let Some(frame) = self.decorations.frame.as_mut() {
             // Configure the window states.
             frame.update_state(configure.state);
 
             match configure.new_size {
-                (Some(width), Some(height)) => {
-                    let (width, height) = frame.subtract_borders(width, height);
+                (None, None) => (fallback_size, true),
+                new_size => {
+                    let fallback_size = frame
+                        .add_borders(fallback_size.width, fallback_size.height)
+                        .into();
+                    let (outer_size, constrain) = configure_size(new_size, fallback_size);
+                    let (width, height) = frame.subtract_borders(
+                        NonZeroU32::new(outer_size.width).unwrap_or(NonZeroU32::MIN),
+                        NonZeroU32::new(outer_size.height).unwrap_or(NonZeroU32::MIN),
+                    );
                     let width = width.map(|w| w.get()).unwrap_or(1);
                     let height = height.map(|h| h.get()).unwrap_or(1);
-                    ((width, height).into(), false)
+                    ((width, height).into(), constrain)
                 }
-                (_, _) if stateless => (self.stateless_size, true),
-                _ => (self.size, true),
             }
         } else {
-            match configure.new_size {
-                (Some(width), Some(height)) => ((width.get(), height.get()).into(), false),
-                _ if stateless => (self.stateless_size, true),
-                _ => (self.size, true),
-            }
+            configure_size(configure.new_size, fallback_size)
         };
 
         // Apply configure bounds only when compositor let the user decide what size to pick.
@@ -357,6 +488,14 @@ impl WindowState {
             // NOTE: `None` is present for the initial configure, thus we must always resize.
             .unwrap_or(true);
 
+        // The window can only be brought back from the minimized state by the user, which
+        // activates it.
+        let activated = new_state.contains(XdgWindowState::ACTIVATED)
+            && !old_state.is_some_and(|state| state.contains(XdgWindowState::ACTIVATED));
+        if activated {
+            self.minimize_requested = false;
+        }
+
         // NOTE: Set the configure before doing a resize, since we query it during it.
         self.last_configure = Some(configure);
 
@@ -378,7 +517,7 @@ impl WindowState {
             None => (None, None),
         };
 
-        if let Some(frame) = self.frame.as_ref() {
+        if let Some(frame) = self.decorations.frame.as_ref() {
             let (width, height) = frame.subtract_borders(
                 configure_bounds.0.unwrap_or(NonZeroU32::new(1).unwrap()),
                 configure_bounds.1.unwrap_or(NonZeroU32::new(1).unwrap()),
@@ -436,8 +575,13 @@ impl WindowState {
         window_id: WindowId,
         updates: &mut Vec<WindowCompositorUpdate>,
     ) -> Option<bool> {
-        match self.frame.as_mut()?.on_click(timestamp, click, pressed)? {
-            FrameAction::Minimize => self.window.set_minimized(),
+        match self
+            .decorations
+            .frame
+            .as_mut()?
+            .on_click(timestamp, click, pressed)?
+        {
+            FrameAction::Minimize => self.set_minimized(),
             FrameAction::Maximize => self.window.set_maximized(),
             FrameAction::UnMaximize => self.window.unset_maximized(),
             FrameAction::Close => WinitState::queue_close(updates, window_id),
@@ -465,7 +609,7 @@ impl WindowState {
     }
 
     pub fn frame_point_left(&mut self) {
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.click_point_left();
         }
     }
@@ -482,7 +626,7 @@ impl WindowState {
         // Take the serial if we had any, so it doesn't stick around.
         let serial = self.has_pending_move.take();
 
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             let cursor = frame.click_point_moved(timestamp, &surface.id(), x, y);
             // If we have a cursor change, that means that cursor is over the decorations,
             // so try to apply move.
@@ -522,7 +666,7 @@ impl WindowState {
         }
 
         // Reload the state on the frame as well.
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.set_resizable(resizable);
         }
 
@@ -535,12 +679,97 @@ impl WindowState {
         !self.seat_focus.is_empty()
     }
 
+    /// Whether the compositor supports text input.
+    #[inline]
+    pub fn ime_supported(&self) -> bool {
+        self.text_input_supported
+    }
+
     /// Whether the IME is allowed.
     #[inline]
     pub fn ime_allowed(&self) -> bool {
         self.ime_allowed
     }
 
+    /// Whether the text inputs are enabled, as in the IME is allowed and not bypassed.
+    #[inline]
+    pub fn ime_active(&self) -> bool {
+        self.ime_allowed && !self.ime_bypass
+    }
+
+    /// Whether the preedit was discarded by the bypass since the last call.
+    #[inline]
+    pub fn take_ime_preedit_discarded(&mut self) -> bool {
+        std::mem::take(&mut self.ime_preedit_discarded)
+    }
+
+    /// Whether to clear the preedit at the start of every IME update.
+    #[inline]
+    pub fn ime_preedit_clear_on_done(&self) -> bool {
+        self.ime_preedit_clear_on_done
+    }
+
+    /// Set whether to clear the preedit at the start of every IME update.
+    #[inline]
+    pub fn set_ime_preedit_clear_on_done(&mut self, clear: bool) {
+        self.ime_preedit_clear_on_done = clear;
+    }
+
+    /// Whether to send the IME changes of every update in a single event.
+    #[inline]
+    pub fn ime_batched_updates(&self) -> bool {
+        self.ime_batched_updates
+    }
+
+    /// Set whether to send the IME changes of every update in a single event.
+    #[inline]
+    pub fn set_ime_batched_updates(&mut self, batched: bool) {
+        self.ime_batched_updates = batched;
+    }
+
+    /// Whether the IME has a preedit shown to the user.
+    #[inline]
+    pub fn ime_composing(&self) -> bool {
+        self.ime_composing
+    }
+
+    /// Set whether the IME has a preedit shown to the user.
+    #[inline]
+    pub fn set_ime_composing(&mut self, composing: bool) {
+        self.ime_composing = composing;
+    }
+
+    /// Whether to send the key presses while the IME composes.
+    #[inline]
+    pub fn ime_key_presses_while_composing(&self) -> bool {
+        self.ime_key_presses_while_composing
+    }
+
+    /// Set whether to send the key presses while the IME composes.
+    #[inline]
+    pub fn set_ime_key_presses_while_composing(&mut self, deliver: bool) {
+        self.ime_key_presses_while_composing = deliver;
+    }
+
+    /// Set whether to hold the resizes until the interactive resize ends.
+    #[inline]
+    pub fn set_debounce_interactive_resize(&mut self, debounce: bool) {
+        self.debounce_interactive_resize = debounce;
+    }
+
+    /// Whether the resize from the last configure, if any, should be reported to the user.
+    pub fn debounce_resize(&mut self, resized: bool) -> bool {
+        let resizing = self
+            .last_configure
+            .as_ref()
+            .is_some_and(|configure| configure.state.contains(XdgWindowState::RESIZING));
+        debounced_resize(
+            self.debounce_interactive_resize && resizing,
+            resized,
+            &mut self.resize_held,
+        )
+    }
+
     /// Get the size of the window.
     #[inline]
     pub fn inner_size(&self) -> LogicalSize<u32> {
@@ -553,25 +782,56 @@ impl WindowState {
         self.last_configure.is_some()
     }
 
+    /// Minimize the window.
+    pub fn set_minimized(&mut self) {
+        self.minimize_requested = true;
+        self.window.set_minimized();
+    }
+
+    /// Guess whether the window is minimized, since the protocol doesn't tell.
+    pub fn is_minimized(&self, now: Instant) -> Option<bool> {
+        let configure = self.last_configure.as_ref()?;
+        minimized_state(
+            configure.state,
+            self.minimize_requested,
+            self.presentation.frame_callback_pending(now),
+        )
+    }
+
+    /// Guess whether the window is visible, from the suspended state and the frame callbacks.
+    pub fn is_visible(&self, now: Instant) -> Option<bool> {
+        let configure = self.last_configure.as_ref()?;
+        Some(!hidden_state(
+            configure.state,
+            self.presentation.frame_callback_pending(now),
+        ))
+    }
+
+    /// Whether the compositor suspended the window, which means that it's not visible.
     #[inline]
-    pub fn is_decorated(&mut self) -> bool {
-        let csd = self
-            .last_configure
+    pub fn is_suspended(&self) -> bool {
+        self.last_configure
             .as_ref()
-            .map(|configure| configure.decoration_mode == DecorationMode::Client)
-            .unwrap_or(false);
-        if let Some(frame) = csd.then_some(self.frame.as_ref()).flatten() {
-            !frame.is_hidden()
-        } else {
-            // Server side decorations.
-            true
-        }
+            .map(|configure| configure.state.contains(XdgWindowState::SUSPENDED))
+            .unwrap_or(false)
+    }
+
+    /// Who draws the decorations, as in the last configure.
+    #[inline]
+    pub fn decoration_mode(&self) -> WaylandDecorationMode {
+        self.decorations.mode()
+    }
+
+    #[inline]
+    pub fn is_decorated(&mut self) -> bool {
+        self.decorations.is_decorated()
     }
 
     /// Get the outer size of the window.
     #[inline]
     pub fn outer_size(&self) -> LogicalSize<u32> {
-        self.frame
+        self.decorations
+            .frame
             .as_ref()
             .map(|frame| frame.add_borders(self.size.width, self.size.height).into())
             .unwrap_or(self.size)
@@ -580,6 +840,7 @@ impl WindowState {
     /// Register pointer on the top-level.
     pub fn pointer_entered(&mut self, added: Weak<ThemedPointer<WinitPointerData>>) {
         self.pointers.push(added);
+        self.cursor_changed = true;
         self.reload_cursor_style();
 
         let mode = self.cursor_grab_mode.user_grab_mode;
@@ -598,11 +859,28 @@ impl WindowState {
         }
 
         self.pointers = new_pointers;
+        self.cursor_changed = true;
+
+        if self.pointers.is_empty() {
+            self.cursor_position = None;
+        }
+    }
+
+    /// Pointer has moved over the main surface.
+    #[inline]
+    pub fn pointer_moved(&mut self, position: LogicalPosition<f64>) {
+        self.cursor_position = Some(position);
+    }
+
+    /// The last position of the pointer on the main surface.
+    #[inline]
+    pub fn cursor_position(&self) -> Option<LogicalPosition<f64>> {
+        self.cursor_position
     }
 
     /// Refresh the decorations frame if it's present returning whether the client should redraw.
     pub fn refresh_frame(&mut self) -> bool {
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             if !frame.is_hidden() && frame.is_dirty() {
                 return frame.draw();
             }
@@ -637,6 +915,46 @@ impl WindowState {
         }
     }
 
+    /// Reissue the input region to the compositor.
+    pub fn reload_input_region(&self) -> Result<(), ExternalError> {
+        let surface = self.window.wl_surface();
+
+        let rects = match self.input_region.surface_rects(self.scale_factor) {
+            Some(rects) => rects,
+            None => {
+                surface.set_input_region(None);
+                return Ok(());
+            }
+        };
+
+        let region = Region::new(&*self.compositor).map_err(|_| {
+            ExternalError::Os(os_error!(crate::platform_impl::OsError::Misc(
+                "failed to set input region."
+            )))
+        })?;
+        for (x, y, width, height) in rects {
+            region.add(x, y, width, height);
+        }
+        surface.set_input_region(Some(region.wl_region()));
+
+        Ok(())
+    }
+
+    /// Set whether the window should accept pointer input.
+    pub fn set_cursor_hittest(&mut self, hittest: bool) -> Result<(), ExternalError> {
+        self.input_region.cursor_hittest = hittest;
+        self.reload_input_region()
+    }
+
+    /// Set the region of the window accepting input, `None` means the whole window.
+    pub fn set_input_region(
+        &mut self,
+        region: Option<Vec<(Position, Size)>>,
+    ) -> Result<(), ExternalError> {
+        self.input_region.region = region;
+        self.reload_input_region()
+    }
+
     /// Try to resize the window when the user can do so.
     pub fn request_inner_size(&mut self, inner_size: Size) -> PhysicalSize<u32> {
         if self
@@ -661,7 +979,7 @@ impl WindowState {
         }
 
         // Update the inner frame.
-        let ((x, y), outer_size) = if let Some(frame) = self.frame.as_mut() {
+        let ((x, y), outer_size) = if let Some(frame) = self.decorations.frame.as_mut() {
             // Resize only visible frame.
             if !frame.is_hidden() {
                 frame.resize(
@@ -705,6 +1023,7 @@ impl WindowState {
     /// Set the cursor icon.
     pub fn set_cursor(&mut self, cursor_icon: CursorIcon) {
         self.selected_cursor = SelectedCursor::Named(cursor_icon);
+        self.cursor_changed = true;
 
         if !self.cursor_visible {
             return;
@@ -734,7 +1053,11 @@ impl WindowState {
 
         let cursor = {
             let mut pool = self.custom_cursor_pool.lock().unwrap();
-            CustomCursor::new(&mut pool, &cursor)
+            match CustomCursor::new(&mut pool, &cursor, &self.shm_formats) {
+                Some(cursor) => cursor,
+                // Keep the current cursor.
+                None => return,
+            }
         };
 
         if self.cursor_visible {
@@ -742,40 +1065,75 @@ impl WindowState {
         }
 
         self.selected_cursor = SelectedCursor::Custom(cursor);
+        self.cursor_changed = true;
     }
 
     fn apply_custom_cursor(&self, cursor: &CustomCursor) {
         self.apply_on_pointer(|pointer, _| {
-            let surface = pointer.surface();
+            attach_cursor_buffer(
+                pointer,
+                cursor.buffer.wl_buffer(),
+                (cursor.w, cursor.h),
+                (cursor.hotspot_x, cursor.hotspot_y),
+            );
+        });
+    }
 
-            let scale = surface
+    /// Whether the cursor or the pointers on the window changed since the last call.
+    pub fn take_cursor_changed(&mut self) -> bool {
+        std::mem::take(&mut self.cursor_changed)
+    }
+
+    /// The themed cursor icon to animate, if any is shown.
+    pub fn animated_cursor(&self) -> Option<CursorIcon> {
+        match self.selected_cursor {
+            SelectedCursor::Named(icon) if self.cursor_visible && !self.pointers.is_empty() => {
+                Some(icon)
+            }
+            _ => None,
+        }
+    }
+
+    /// Show the frame of the cursor animation after `millis` of it on every pointer.
+    ///
+    /// Returns the time until the next frame, `None` means there's nothing to animate.
+    pub fn animate_cursor(
+        &self,
+        themes: &mut CursorThemes,
+        icon: CursorIcon,
+        millis: u32,
+    ) -> Option<Duration> {
+        let mut next_frame: Option<u32> = None;
+        self.apply_on_pointer(|pointer, _| {
+            let scale = pointer
+                .surface()
                 .data::<SurfaceData>()
                 .unwrap()
                 .surface_data()
                 .scale_factor();
 
-            surface.set_buffer_scale(scale);
-            surface.attach(Some(cursor.buffer.wl_buffer()), 0, 0);
-            if surface.version() >= 4 {
-                surface.damage_buffer(0, 0, cursor.w, cursor.h);
-            } else {
-                surface.damage(0, 0, cursor.w / scale, cursor.h / scale);
-            }
-            surface.commit();
-
-            let serial = pointer
-                .pointer()
-                .data::<WinitPointerData>()
-                .and_then(|data| data.pointer_data().latest_enter_serial())
-                .unwrap();
-
-            pointer.pointer().set_cursor(
-                serial,
-                Some(surface),
-                cursor.hotspot_x / scale,
-                cursor.hotspot_y / scale,
-            );
+            let cursor = match themes.get_cursor(&self.connection, icon, scale as u32) {
+                Some(cursor) => cursor,
+                None => return,
+            };
+
+            let delays: Vec<u32> = (0..cursor.image_count())
+                .map(|index| cursor[index].delay())
+                .collect();
+            let (index, remaining) = match animation_frame(&delays, millis) {
+                Some(frame) => frame,
+                None => return,
+            };
+
+            let image = &cursor[index];
+            let (w, h) = image.dimensions();
+            let (hx, hy) = image.hotspot();
+            attach_cursor_buffer(pointer, image, (w as i32, h as i32), (hx as i32, hy as i32));
+
+            next_frame = Some(next_frame.map_or(remaining, |next| next.min(remaining)));
         });
+
+        next_frame.map(|millis| Duration::from_millis(millis.into()))
     }
 
     /// Set maximum inner window size.
@@ -787,6 +1145,7 @@ impl WindowState {
 
         // Add the borders.
         let size = self
+            .decorations
             .frame
             .as_ref()
             .map(|frame| frame.add_borders(size.width, size.height).into())
@@ -799,7 +1158,8 @@ impl WindowState {
     /// Set maximum inner window size.
     pub fn set_max_inner_size(&mut self, size: Option<LogicalSize<u32>>) {
         let size = size.map(|size| {
-            self.frame
+            self.decorations
+                .frame
                 .as_ref()
                 .map(|frame| frame.add_borders(size.width, size.height).into())
                 .unwrap_or(size)
@@ -813,15 +1173,31 @@ impl WindowState {
     pub fn set_theme(&mut self, theme: Option<Theme>) {
         self.theme = theme;
         #[cfg(feature = "sctk-adwaita")]
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.set_config(into_sctk_adwaita_config(theme))
         }
     }
 
-    /// The current theme for CSD decorations.
+    /// Set the color scheme preferred by the system, returning whether the theme changed.
+    pub fn set_system_theme(&mut self, theme: Option<Theme>) -> bool {
+        self.system_theme = theme;
+        if self.theme.is_some() {
+            return false;
+        }
+
+        // Let the frame pick up the new preference.
+        #[cfg(feature = "sctk-adwaita")]
+        if let Some(frame) = self.decorations.frame.as_mut() {
+            frame.set_config(into_sctk_adwaita_config(None))
+        }
+
+        true
+    }
+
+    /// The current theme, falling back to the one preferred by the system.
     #[inline]
     pub fn theme(&self) -> Option<Theme> {
-        self.theme
+        self.theme.or(self.system_theme)
     }
 
     /// Set the cursor grabbing state on the top-level.
@@ -910,6 +1286,7 @@ impl WindowState {
     /// Set the visibility state of the cursor.
     pub fn set_cursor_visible(&mut self, cursor_visible: bool) {
         self.cursor_visible = cursor_visible;
+        self.cursor_changed = true;
 
         if self.cursor_visible {
             match &self.selected_cursor {
@@ -936,23 +1313,11 @@ impl WindowState {
 
         self.decorate = decorate;
 
-        match self
-            .last_configure
-            .as_ref()
-            .map(|configure| configure.decoration_mode)
-        {
-            Some(DecorationMode::Server) if !self.decorate => {
-                // To disable decorations we should request client and hide the frame.
-                self.window
-                    .request_decoration_mode(Some(DecorationMode::Client))
-            }
-            _ if self.decorate => self
-                .window
-                .request_decoration_mode(Some(DecorationMode::Server)),
-            _ => (),
+        if let Some(mode) = decoration_mode_request(self.decorations.mode, decorate) {
+            self.window.request_decoration_mode(Some(mode));
         }
 
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.set_hidden(!decorate);
             // Force the resize.
             self.resize(self.size);
@@ -973,43 +1338,133 @@ impl WindowState {
 
     /// Returns `true` if the requested state was applied.
     pub fn set_ime_allowed(&mut self, allowed: bool) -> bool {
+        // Keep IME disallowed, since no text input will ever enter the window. That was
+        // already logged when binding the globals.
+        if allowed && !self.text_input_supported {
+            return false;
+        }
+
         self.ime_allowed = allowed;
+        self.ime_composing &= allowed;
 
-        let mut applied = false;
-        for text_input in &self.text_inputs {
-            applied = true;
-            if allowed {
-                text_input.enable();
-                text_input.set_content_type_by_purpose(self.ime_purpose);
-            } else {
-                text_input.disable();
-            }
-            text_input.commit();
+        // The text inputs are enabled or disabled on the next flush, so toggling IME several
+        // times in a row only sends the net change.
+        !self.text_inputs.is_empty()
+    }
+
+    /// Disable the text inputs while `bypass` is set, so the keys aren't taken by the IME.
+    ///
+    /// Returns `true` when the text inputs were disabled while composing, discarding the preedit.
+    pub fn set_ime_bypass(&mut self, bypass: bool) -> bool {
+        if self.ime_bypass == bypass {
+            return false;
         }
 
-        applied
+        self.ime_bypass = bypass;
+        if !self.ime_allowed || self.text_inputs.is_empty() {
+            return false;
+        }
+
+        // The text inputs are disabled on the next flush.
+        let was_composing = self.ime_composing;
+        self.ime_preedit_discarded |= bypass;
+        self.ime_composing &= !bypass;
+        bypass && was_composing
+    }
+
+    /// Whether the text inputs of the window are enabled, and should stay so.
+    pub fn ime_text_inputs_enabled(&self) -> bool {
+        self.pending_ime_state.enabled() && self.ime_active()
+    }
+
+    /// Enable the text input, restoring the IME state the user requested.
+    pub fn enable_text_input(&self, text_input: &ZwpTextInputV3) {
+        text_input.enable();
+        self.pending_ime_state.full_update().send(text_input);
     }
 
     /// Set the IME position.
-    pub fn set_ime_cursor_area(&self, position: LogicalPosition<u32>, size: LogicalSize<u32>) {
+    pub fn set_ime_cursor_area(&mut self, position: Position, size: Size) {
+        self.ime_cursor_area = Some((position, size));
+
         // FIXME: This won't fly unless user will have a way to request IME window per seat, since
         // the ime windows will be overlapping, but winit doesn't expose API to specify for
         // which seat we're setting IME position.
-        let (x, y) = (position.x as i32, position.y as i32);
-        let (width, height) = (size.width as i32, size.height as i32);
-        for text_input in self.text_inputs.iter() {
-            text_input.set_cursor_rectangle(x, y, width, height);
-            text_input.commit();
+        let (x, y, width, height) = ime_cursor_rectangle(position, size, self.scale_factor());
+        self.pending_ime_state
+            .set_cursor_rectangle(x, y, width, height);
+    }
+
+    /// Get the IME position.
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.ime_cursor_area
+    }
+
+    /// Set the text around the IME cursor.
+    pub fn set_ime_surrounding_text(&mut self, text: String, cursor: usize, anchor: usize) {
+        match SurroundingText::new(text, cursor, anchor) {
+            Some(surrounding_text) => self
+                .pending_ime_state
+                .set_surrounding_text(surrounding_text),
+            None => warn!("the IME surrounding text cursor or anchor isn't on a char boundary"),
         }
     }
 
+    /// The IME committed or deleted text, so the next surrounding text is the result of it.
+    pub fn ime_edited(&mut self) {
+        self.pending_ime_state.ime_edited();
+    }
+
+    /// The most bytes, up to `before` and `after`, which could be deleted around the selection in
+    /// the IME surrounding text, if it was set.
+    pub fn ime_surrounding_deletable(&self, before: usize, after: usize) -> Option<(usize, usize)> {
+        self.pending_ime_state.surrounding_deletable(before, after)
+    }
+
+    /// Whether the IME surrounding text has a selection, if it was set.
+    pub fn ime_surrounding_selected(&self) -> Option<bool> {
+        self.pending_ime_state.surrounding_selected()
+    }
+
     /// Set the IME purpose.
     pub fn set_ime_purpose(&mut self, purpose: ImePurpose) {
         self.ime_purpose = purpose;
+        self.pending_ime_state.set_content_type(purpose);
+    }
+
+    /// Set the IME content hints.
+    pub fn set_ime_content_hints(&mut self, hints: ImeContentHints) {
+        self.pending_ime_state.set_content_hints(hints);
+    }
+
+    /// Send the IME state changed since the last flush to the text inputs.
+    pub fn flush_ime_state(&mut self) {
+        match self.pending_ime_state.take_enabled(self.ime_active()) {
+            Some(true) => {
+                for text_input in &self.text_inputs {
+                    self.enable_text_input(text_input);
+                }
 
-        for text_input in &self.text_inputs {
-            text_input.set_content_type_by_purpose(purpose);
-            text_input.commit();
+                // The whole state was just sent.
+                self.pending_ime_state.take();
+                return;
+            }
+            Some(false) => {
+                for text_input in &self.text_inputs {
+                    text_input.disable();
+                    commit_state(text_input);
+                }
+                return;
+            }
+            // The state is sent in full once IME is allowed and not bypassed.
+            None if !self.ime_active() => return,
+            None => (),
+        }
+
+        if let Some(update) = self.pending_ime_state.take() {
+            for text_input in &self.text_inputs {
+                update.send(text_input);
+            }
         }
     }
 
@@ -1018,6 +1473,12 @@ impl WindowState {
         self.ime_purpose
     }
 
+    /// Whether the compositor scales the window by a fraction.
+    #[inline]
+    pub fn has_fractional_scale(&self) -> bool {
+        self.fractional_scale.is_some() && self.scale_factor.fract() != 0.
+    }
+
     /// Set the scale factor for the given window.
     #[inline]
     pub fn set_scale_factor(&mut self, scale_factor: f64) {
@@ -1028,9 +1489,19 @@ impl WindowState {
             let _ = self.window.set_buffer_scale(self.scale_factor as _);
         }
 
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.set_scaling_factor(scale_factor);
         }
+
+        // The input region could be in physical units.
+        if self.input_region.region.is_some() && self.reload_input_region().is_err() {
+            warn!("Failed to update the input region.");
+        }
+
+        // So could the IME cursor area.
+        if let Some((position, size)) = self.ime_cursor_area {
+            self.set_ime_cursor_area(position, size);
+        }
     }
 
     /// Make window background blurred
@@ -1068,7 +1539,7 @@ impl WindowState {
         }
 
         // Update the CSD title.
-        if let Some(frame) = self.frame.as_mut() {
+        if let Some(frame) = self.decorations.frame.as_mut() {
             frame.set_title(&title);
         }
 
@@ -1091,14 +1562,43 @@ impl WindowState {
         }
     }
 
-    /// The text input left the top-level.
+    /// The text input left the top-level, returning whether it was on it.
     #[inline]
-    pub fn text_input_left(&mut self, text_input: &ZwpTextInputV3) {
-        if let Some(position) = self.text_inputs.iter().position(|t| t == text_input) {
-            self.text_inputs.remove(position);
+    pub fn text_input_left(&mut self, text_input: &ZwpTextInputV3) -> bool {
+        match self.text_inputs.iter().position(|t| t == text_input) {
+            Some(position) => {
+                self.text_inputs.remove(position);
+                true
+            }
+            None => false,
         }
     }
 
+    /// Whether a text input is on the window.
+    #[inline]
+    pub fn has_text_inputs(&self) -> bool {
+        !self.text_inputs.is_empty()
+    }
+
+    /// The IME `event` to send to the user, unless it repeats the last `Enabled` or `Disabled`.
+    #[inline]
+    pub fn filter_ime_enablement(&mut self, event: Ime) -> Option<Ime> {
+        self.ime_enablement.filter(event)
+    }
+
+    /// Hold the `Disabled` sent when the last text input left the window, returning the time
+    /// it's sent at, unless a text input enters the window before.
+    #[inline]
+    pub fn hold_ime_disabled(&mut self, now: Instant) -> Option<Instant> {
+        self.ime_enablement.hold_disabled(now)
+    }
+
+    /// The held `Disabled` to send by `now`.
+    #[inline]
+    pub fn settle_ime_enablement(&mut self, now: Instant) -> Option<Ime> {
+        self.ime_enablement.settle(now)
+    }
+
     /// Get the cached title.
     #[inline]
     pub fn title(&self) -> &str {
@@ -1144,6 +1644,91 @@ impl GrabState {
     }
 }
 
+/// The suspension of a window and its frame callbacks, which decide when to send the redraws.
+///
+/// The redraws requested while they can't be sent are held in the [`WindowRequests`] until they
+/// can.
+#[derive(Default, Debug)]
+pub struct Presentation {
+    /// Whether the window received its initial configure.
+    configured: bool,
+
+    /// Whether the compositor suspended the window.
+    suspended: bool,
+
+    /// The state of the frame callback.
+    frame_callback_state: FrameCallbackState,
+
+    /// The time the frame callback in flight was requested.
+    frame_callback_requested_at: Option<Instant>,
+}
+
+impl Presentation {
+    /// Apply the `suspended` state of a configure, which also demands a redraw.
+    ///
+    /// Returns whether the window is now occluded, when it changed.
+    pub fn configure(&mut self, suspended: bool, requests: &WindowRequests) -> Option<bool> {
+        self.configured = true;
+        let was_suspended = mem::replace(&mut self.suspended, suspended);
+
+        // The compositor doesn't send the frame callbacks while the window is suspended, so don't
+        // wait for the one requested before to redraw after resuming.
+        if was_suspended && !suspended {
+            self.frame_callback_reset();
+        }
+
+        // NOTE: configure demands wl_surface::commit, however winit doesn't commit on behalf of the
+        // users, since it can break a lot of things, thus it'll ask users to redraw instead.
+        requests.redraw_requested.store(true, Ordering::Relaxed);
+
+        (suspended != was_suspended).then_some(suspended)
+    }
+
+    /// Whether the redraw could be sent to the user.
+    pub fn can_redraw(&self) -> bool {
+        redraw_allowed(self.configured, self.suspended, self.frame_callback_state)
+    }
+
+    /// Take the redraw requested in `requests` when it can be sent, resetting the frame callbacks.
+    pub fn take_redraw(&mut self, requests: &WindowRequests) -> bool {
+        if !self.can_redraw() {
+            return false;
+        }
+
+        self.frame_callback_reset();
+        requests.take_redraw_requested()
+    }
+
+    /// Note a frame callback requested at `now`, returning whether to request it from the
+    /// compositor, unless one is already in flight.
+    pub fn request_frame_callback(&mut self, now: Instant) -> bool {
+        if self.frame_callback_state == FrameCallbackState::Requested {
+            return false;
+        }
+
+        self.frame_callback_state = FrameCallbackState::Requested;
+        self.frame_callback_requested_at = Some(now);
+        true
+    }
+
+    /// The frame callback was received, but not yet sent to the user.
+    pub fn frame_callback_received(&mut self) {
+        self.frame_callback_state = FrameCallbackState::Received;
+        self.frame_callback_requested_at = None;
+    }
+
+    /// For how long the frame callback in flight is pending.
+    pub fn frame_callback_pending(&self, now: Instant) -> Option<Duration> {
+        self.frame_callback_requested_at
+            .map(|requested_at| now.saturating_duration_since(requested_at))
+    }
+
+    fn frame_callback_reset(&mut self) {
+        self.frame_callback_state = FrameCallbackState::None;
+        self.frame_callback_requested_at = None;
+    }
+}
+
 /// The state of the frame callback.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FrameCallbackState {
@@ -1180,3 +1765,650 @@ fn into_sctk_adwaita_config(theme: Option<Theme>) -> sctk_adwaita::FrameConfig {
         None => sctk_adwaita::FrameConfig::auto(),
     }
 }
+
+/// The client side decorations of a window, following the decoration mode negotiated with the
+/// compositor.
+struct Decorations<F> {
+    /// The frame, only there while the compositor leaves the decorations to us.
+    frame: Option<F>,
+
+    /// The decoration mode of the last configure.
+    mode: Option<DecorationMode>,
+
+    /// Whether the frame failed to be created, so we don't try to create it on each configure.
+    csd_fails: bool,
+}
+
+impl<F: DecorationsFrame> Decorations<F> {
+    fn new() -> Self {
+        Self {
+            frame: None,
+            mode: None,
+            csd_fails: false,
+        }
+    }
+
+    /// Follow the decoration `mode` of a configure, creating the frame with `create_frame`, when
+    /// there's a way to, once the compositor leaves the decorations to us, and dropping it once
+    /// it draws them.
+    fn configure<E: std::fmt::Display>(
+        &mut self,
+        mode: DecorationMode,
+        decorate: bool,
+        create_frame: Option<impl FnOnce() -> Result<F, E>>,
+    ) {
+        self.mode = Some(mode);
+
+        if mode == DecorationMode::Server {
+            // Drop the frame for server side decorations to save resources.
+            self.frame = None;
+        } else if let Some(frame) = self.frame.as_mut() {
+            // The compositor could keep us on client side decorations after we've asked for
+            // the server ones, so make sure the frame matches what the user wants.
+            if frame.is_hidden() == decorate {
+                frame.set_hidden(!decorate);
+            }
+        } else if let Some(create_frame) = create_frame.filter(|_| !self.csd_fails) {
+            match create_frame() {
+                Ok(mut frame) => {
+                    // Hide the frame if we were asked to not decorate.
+                    frame.set_hidden(!decorate);
+                    self.frame = Some(frame);
+                }
+                Err(err) => {
+                    warn!("Failed to create client side decorations frame: {err}");
+                    self.csd_fails = true;
+                }
+            }
+        }
+    }
+
+    /// Who draws the decorations, as in the last configure.
+    fn mode(&self) -> WaylandDecorationMode {
+        match self.mode {
+            Some(DecorationMode::Server) => WaylandDecorationMode::Server,
+            _ => WaylandDecorationMode::Client,
+        }
+    }
+
+    /// Whether the decorations are shown, by us or by the compositor.
+    fn is_decorated(&self) -> bool {
+        let csd = self.mode == Some(DecorationMode::Client);
+        if let Some(frame) = csd.then_some(self.frame.as_ref()).flatten() {
+            !frame.is_hidden()
+        } else {
+            // Server side decorations.
+            true
+        }
+    }
+}
+
+/// The decoration mode to request from the compositor when the user changes decorations.
+fn decoration_mode_request(
+    current_mode: Option<DecorationMode>,
+    decorate: bool,
+) -> Option<DecorationMode> {
+    match current_mode {
+        // To disable decorations we should request client and hide the frame.
+        Some(DecorationMode::Server) if !decorate => Some(DecorationMode::Client),
+        _ if decorate => Some(DecorationMode::Server),
+        _ => None,
+    }
+}
+
+/// The minimized state from the window `state`, whether we minimized the window since it was
+/// last activated, and for how long a frame callback is pending.
+///
+/// Being hidden could also mean being on another workspace or covered, so the window is only
+/// considered minimized when we minimized it and it's hidden since.
+fn minimized_state(
+    state: XdgWindowState,
+    minimize_requested: bool,
+    frame_callback_pending: Option<Duration>,
+) -> Option<bool> {
+    if state.contains(XdgWindowState::ACTIVATED) {
+        return Some(false);
+    }
+
+    (minimize_requested && hidden_state(state, frame_callback_pending)).then_some(true)
+}
+
+/// Whether the window is hidden, from the window `state` and for how long a frame callback is
+/// pending, which the compositor holds back while it doesn't show the window.
+fn hidden_state(state: XdgWindowState, frame_callback_pending: Option<Duration>) -> bool {
+    !state.contains(XdgWindowState::ACTIVATED)
+        && (state.contains(XdgWindowState::SUSPENDED)
+            || frame_callback_pending.is_some_and(|pending| pending >= HIDDEN_FRAME_CALLBACK_DELAY))
+}
+
+/// The size from the configure, taking the dimensions the compositor left to us, which it sends
+/// as zero, from `fallback`, and whether any was taken.
+fn configure_size(
+    new_size: (Option<NonZeroU32>, Option<NonZeroU32>),
+    fallback: LogicalSize<u32>,
+) -> (LogicalSize<u32>, bool) {
+    let (width, height) = new_size;
+    let size = LogicalSize::new(
+        width.map_or(fallback.width, NonZeroU32::get),
+        height.map_or(fallback.height, NonZeroU32::get),
+    );
+    (size, width.is_none() || height.is_none())
+}
+
+/// Whether the redraw could be sent for a window in the given state.
+fn redraw_allowed(
+    configured: bool,
+    suspended: bool,
+    frame_callback_state: FrameCallbackState,
+) -> bool {
+    configured && !suspended && frame_callback_state != FrameCallbackState::Requested
+}
+
+/// Whether to report a resize, holding it in `held` while `hold` is set, so the held resize is
+/// reported along with the next one.
+fn debounced_resize(hold: bool, resized: bool, held: &mut bool) -> bool {
+    if hold {
+        *held |= resized;
+        return false;
+    }
+
+    let report = resized || *held;
+    *held = false;
+    report
+}
+
+/// The region of a window accepting input, and whether it accepts pointer input at all.
+#[derive(Debug, Clone, PartialEq)]
+struct InputRegion {
+    /// Whether the window accepts pointer input at all.
+    cursor_hittest: bool,
+
+    /// The region of the window accepting input, `None` means the whole window.
+    region: Option<Vec<(Position, Size)>>,
+}
+
+impl InputRegion {
+    fn new() -> Self {
+        Self {
+            cursor_hittest: true,
+            region: None,
+        }
+    }
+
+    /// The rectangles of the input region to set on the surface, in the surface local logical
+    /// coordinates, `None` meaning the whole window.
+    fn surface_rects(&self, scale_factor: f64) -> Option<Vec<(i32, i32, i32, i32)>> {
+        effective_input_region(self.cursor_hittest, self.region.as_deref())
+            .map(|rects| input_region_rects(rects, scale_factor).collect())
+    }
+}
+
+/// The input region to set on the surface, `None` meaning the whole window.
+fn effective_input_region(
+    cursor_hittest: bool,
+    input_region: Option<&[(Position, Size)]>,
+) -> Option<&[(Position, Size)]> {
+    if cursor_hittest {
+        input_region
+    } else {
+        // An empty region makes the whole window click-through.
+        Some(&[])
+    }
+}
+
+/// Convert the input region rectangles to the surface local logical coordinates.
+fn input_region_rects(
+    region: &[(Position, Size)],
+    scale_factor: f64,
+) -> impl Iterator<Item = (i32, i32, i32, i32)> + '_ {
+    region.iter().map(move |(position, size)| {
+        let position: LogicalPosition<i32> = position.to_logical(scale_factor);
+        let size: LogicalSize<i32> = size.to_logical(scale_factor);
+        (position.x, position.y, size.width, size.height)
+    })
+}
+
+/// Show the `buffer` on the pointer's cursor surface, with the size and hotspot in buffer
+/// coordinates.
+fn attach_cursor_buffer(
+    pointer: &ThemedPointer<WinitPointerData>,
+    buffer: &WlBuffer,
+    (w, h): (i32, i32),
+    (hotspot_x, hotspot_y): (i32, i32),
+) {
+    let surface = pointer.surface();
+
+    let scale = surface
+        .data::<SurfaceData>()
+        .unwrap()
+        .surface_data()
+        .scale_factor();
+
+    surface.set_buffer_scale(scale);
+    surface.attach(Some(buffer), 0, 0);
+    if surface.version() >= 4 {
+        surface.damage_buffer(0, 0, w, h);
+    } else {
+        surface.damage(0, 0, w / scale, h / scale);
+    }
+    surface.commit();
+
+    let serial = pointer
+        .pointer()
+        .data::<WinitPointerData>()
+        .and_then(|data| data.pointer_data().latest_enter_serial())
+        .unwrap();
+
+    pointer
+        .pointer()
+        .set_cursor(serial, Some(surface), hotspot_x / scale, hotspot_y / scale);
+}
+
+/// The IME cursor area as the surface local rectangle the compositor expects.
+fn ime_cursor_rectangle(position: Position, size: Size, scale_factor: f64) -> (i32, i32, i32, i32) {
+    let position: LogicalPosition<u32> = position.to_logical(scale_factor);
+    let size: LogicalSize<u32> = size.to_logical(scale_factor);
+    (
+        position.x as i32,
+        position.y as i32,
+        size.width as i32,
+        size.height as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use sctk::reexports::csd_frame::WindowManagerCapabilities;
+
+    use super::*;
+
+    use crate::dpi::{PhysicalPosition, PhysicalSize};
+
+    #[test]
+    fn minimized_only_when_hidden_after_minimizing() {
+        let pending = Some(HIDDEN_FRAME_CALLBACK_DELAY);
+
+        // Activated windows are visible.
+        assert_eq!(
+            minimized_state(XdgWindowState::ACTIVATED, true, pending),
+            Some(false)
+        );
+
+        // Hidden windows could be on another workspace.
+        assert_eq!(
+            minimized_state(XdgWindowState::SUSPENDED, false, None),
+            None
+        );
+        assert_eq!(
+            minimized_state(XdgWindowState::empty(), false, pending),
+            None
+        );
+
+        // Not hidden yet, or not rendering.
+        assert_eq!(
+            minimized_state(XdgWindowState::empty(), true, Some(Duration::ZERO)),
+            None
+        );
+        assert_eq!(minimized_state(XdgWindowState::empty(), true, None), None);
+
+        assert_eq!(
+            minimized_state(XdgWindowState::SUSPENDED, true, None),
+            Some(true)
+        );
+        assert_eq!(
+            minimized_state(XdgWindowState::empty(), true, pending),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn hidden_when_suspended_or_not_presented() {
+        let pending = Some(HIDDEN_FRAME_CALLBACK_DELAY);
+
+        assert!(hidden_state(XdgWindowState::SUSPENDED, None));
+        assert!(hidden_state(XdgWindowState::empty(), pending));
+
+        // Activated windows are visible, and the frame callbacks take a moment.
+        assert!(!hidden_state(XdgWindowState::ACTIVATED, pending));
+        assert!(!hidden_state(XdgWindowState::empty(), Some(Duration::ZERO)));
+        assert!(!hidden_state(XdgWindowState::empty(), None));
+    }
+
+    #[test]
+    fn zero_configure_size_is_picked_by_us() {
+        let fallback = LogicalSize::new(800, 600);
+        let size = |value| NonZeroU32::new(value);
+
+        // A 0x0 configure keeps the size we have.
+        assert_eq!(configure_size((None, None), fallback), (fallback, true));
+
+        // Only the zero dimension is up to us.
+        assert_eq!(
+            configure_size((size(1024), None), fallback),
+            (LogicalSize::new(1024, 600), true)
+        );
+        assert_eq!(
+            configure_size((None, size(300)), fallback),
+            (LogicalSize::new(800, 300), true)
+        );
+
+        assert_eq!(
+            configure_size((size(1024), size(300)), fallback),
+            (LogicalSize::new(1024, 300), false)
+        );
+    }
+
+    #[test]
+    fn redraw_paused_while_suspended() {
+        assert!(redraw_allowed(true, false, FrameCallbackState::None));
+        assert!(!redraw_allowed(false, false, FrameCallbackState::None));
+
+        // The frame callback requested before the suspension never arrives.
+        assert!(!redraw_allowed(true, true, FrameCallbackState::Requested));
+        assert!(!redraw_allowed(true, false, FrameCallbackState::Requested));
+
+        // Resuming resets it, so the redraws are delivered again.
+        assert!(!redraw_allowed(true, true, FrameCallbackState::None));
+        assert!(redraw_allowed(true, false, FrameCallbackState::None));
+    }
+
+    #[test]
+    fn redraw_held_while_suspended() {
+        let requests = WindowRequests {
+            closed: AtomicBool::new(false),
+            redraw_requested: AtomicBool::new(true),
+        };
+        let mut presentation = Presentation::default();
+
+        // The redraw waits for the initial configure.
+        assert!(!presentation.take_redraw(&requests));
+        assert_eq!(presentation.configure(false, &requests), None);
+        assert!(presentation.take_redraw(&requests));
+        assert!(!presentation.take_redraw(&requests));
+
+        // The user presents, and the compositor suspends the window before the frame callback.
+        assert!(presentation.request_frame_callback(Instant::now()));
+        assert_eq!(presentation.configure(true, &requests), Some(true));
+        assert!(!presentation.take_redraw(&requests));
+        assert_eq!(presentation.configure(true, &requests), None);
+
+        // The redraw requested meanwhile is held until the window is resumed.
+        requests.redraw_requested.store(true, Ordering::Relaxed);
+        assert!(!presentation.take_redraw(&requests));
+        assert!(requests.redraw_requested.load(Ordering::Relaxed));
+
+        assert_eq!(presentation.configure(false, &requests), Some(false));
+        assert!(presentation.take_redraw(&requests));
+        assert!(!presentation.take_redraw(&requests));
+    }
+
+    #[test]
+    fn single_resize_at_interactive_resize_end() {
+        let mut held = false;
+
+        // Every configure during the resize changes the size.
+        assert!(!debounced_resize(true, true, &mut held));
+        assert!(!debounced_resize(true, true, &mut held));
+        assert!(!debounced_resize(true, false, &mut held));
+
+        // The configure ending the resize could keep the last size.
+        assert!(debounced_resize(false, false, &mut held));
+        assert!(!debounced_resize(false, false, &mut held));
+
+        // Resizes outside of the interactive resize aren't held.
+        assert!(debounced_resize(false, true, &mut held));
+
+        // Nothing is reported for an interactive resize which didn't change the size.
+        assert!(!debounced_resize(true, false, &mut held));
+        assert!(!debounced_resize(false, false, &mut held));
+    }
+
+    /// A frame which only tracks whether it's hidden.
+    #[derive(Default)]
+    struct TestFrame {
+        hidden: bool,
+    }
+
+    impl DecorationsFrame for TestFrame {
+        fn on_click(&mut self, _: Duration, _: FrameClick, _: bool) -> Option<FrameAction> {
+            None
+        }
+
+        fn click_point_moved(
+            &mut self,
+            _: Duration,
+            _: &ObjectId,
+            _: f64,
+            _: f64,
+        ) -> Option<CursorIcon> {
+            None
+        }
+
+        fn click_point_left(&mut self) {}
+
+        fn update_state(&mut self, _: XdgWindowState) {}
+
+        fn update_wm_capabilities(&mut self, _: WindowManagerCapabilities) {}
+
+        fn resize(&mut self, _: NonZeroU32, _: NonZeroU32) {}
+
+        fn set_scaling_factor(&mut self, _: f64) {}
+
+        fn location(&self) -> (i32, i32) {
+            (0, 0)
+        }
+
+        fn subtract_borders(
+            &self,
+            width: NonZeroU32,
+            height: NonZeroU32,
+        ) -> (Option<NonZeroU32>, Option<NonZeroU32>) {
+            (Some(width), Some(height))
+        }
+
+        fn add_borders(&self, width: u32, height: u32) -> (u32, u32) {
+            (width, height)
+        }
+
+        fn is_dirty(&self) -> bool {
+            false
+        }
+
+        fn set_hidden(&mut self, hidden: bool) {
+            self.hidden = hidden;
+        }
+
+        fn is_hidden(&self) -> bool {
+            self.hidden
+        }
+
+        fn set_resizable(&mut self, _: bool) {}
+
+        fn draw(&mut self) -> bool {
+            false
+        }
+
+        fn set_title(&mut self, _: impl Into<String>) {}
+    }
+
+    fn create_frame() -> Option<impl FnOnce() -> Result<TestFrame, &'static str>> {
+        Some(|| Ok(TestFrame::default()))
+    }
+
+    #[test]
+    fn frame_follows_decoration_mode() {
+        let mut decorations = Decorations::<TestFrame>::new();
+        assert_eq!(decorations.mode(), WaylandDecorationMode::Client);
+
+        decorations.configure(DecorationMode::Server, true, create_frame());
+        assert!(decorations.frame.is_none());
+        assert_eq!(decorations.mode(), WaylandDecorationMode::Server);
+
+        // Switching to client side decorations creates the frame.
+        decorations.configure(DecorationMode::Client, true, create_frame());
+        assert!(!decorations.frame.as_ref().unwrap().is_hidden());
+        assert_eq!(decorations.mode(), WaylandDecorationMode::Client);
+        assert!(decorations.is_decorated());
+
+        // And switching back drops it.
+        decorations.configure(DecorationMode::Server, true, create_frame());
+        assert!(decorations.frame.is_none());
+        assert_eq!(decorations.mode(), WaylandDecorationMode::Server);
+        assert!(decorations.is_decorated());
+
+        // Undecorated windows get a hidden frame, which is kept over the next configures.
+        decorations.configure(DecorationMode::Client, false, create_frame());
+        assert!(decorations.frame.as_ref().unwrap().is_hidden());
+        assert!(!decorations.is_decorated());
+        let no_frame: Option<fn() -> Result<TestFrame, &'static str>> = None;
+        decorations.configure(DecorationMode::Client, true, no_frame);
+        assert!(!decorations.frame.as_ref().unwrap().is_hidden());
+        assert!(decorations.is_decorated());
+    }
+
+    #[test]
+    fn frame_creation_not_retried() {
+        let mut decorations = Decorations::<TestFrame>::new();
+
+        decorations.configure(DecorationMode::Client, true, Some(|| Err("no shm")));
+        assert!(decorations.frame.is_none());
+        assert_eq!(decorations.mode(), WaylandDecorationMode::Client);
+
+        decorations.configure(DecorationMode::Client, true, create_frame());
+        assert!(decorations.frame.is_none());
+    }
+
+    #[test]
+    fn toggle_decorations_twice() {
+        // Start with server side decorations and disable them.
+        let mode = decoration_mode_request(Some(DecorationMode::Server), false);
+        assert_eq!(mode, Some(DecorationMode::Client));
+
+        // The compositor switched to client side, so enable them back.
+        let mode = decoration_mode_request(Some(DecorationMode::Client), true);
+        assert_eq!(mode, Some(DecorationMode::Server));
+
+        // Disabling client side decorations only hides the frame.
+        assert_eq!(
+            decoration_mode_request(Some(DecorationMode::Client), false),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_input_region() {
+        assert_eq!(input_region_rects(&[], 2.).count(), 0);
+    }
+
+    #[test]
+    fn cursor_hittest_toggling() {
+        let region = [(
+            LogicalPosition::new(10., 20.).into(),
+            LogicalSize::new(100., 50.).into(),
+        )];
+
+        // Click-through drops any input region.
+        assert_eq!(effective_input_region(false, None), Some(&[][..]));
+        assert_eq!(effective_input_region(false, Some(&region)), Some(&[][..]));
+
+        // Hit testing back restores the previous input region.
+        assert_eq!(effective_input_region(true, None), None);
+        assert_eq!(
+            effective_input_region(true, Some(&region)),
+            Some(&region[..])
+        );
+    }
+
+    #[test]
+    fn input_region_follows_the_user() {
+        let mut input_region = InputRegion::new();
+        assert_eq!(input_region.surface_rects(2.), None);
+
+        input_region.region = Some(vec![(
+            PhysicalPosition::new(20, 40).into(),
+            PhysicalSize::new(200, 100).into(),
+        )]);
+        assert_eq!(
+            input_region.surface_rects(2.),
+            Some(vec![(10, 20, 100, 50)])
+        );
+
+        // Physical regions follow the scale factor.
+        assert_eq!(
+            input_region.surface_rects(1.),
+            Some(vec![(20, 40, 200, 100)])
+        );
+
+        // Unsetting the region gives the whole window back.
+        input_region.region = None;
+        assert_eq!(input_region.surface_rects(2.), None);
+
+        // The region set while click-through only applies once hit testing is back.
+        input_region.cursor_hittest = false;
+        assert_eq!(input_region.surface_rects(2.), Some(Vec::new()));
+        input_region.region = Some(vec![(
+            LogicalPosition::new(10., 20.).into(),
+            LogicalSize::new(100., 50.).into(),
+        )]);
+        assert_eq!(input_region.surface_rects(2.), Some(Vec::new()));
+        input_region.cursor_hittest = true;
+        assert_eq!(
+            input_region.surface_rects(2.),
+            Some(vec![(10, 20, 100, 50)])
+        );
+
+        // Unsetting the region while click-through stays click-through, and then gives the whole
+        // window back.
+        input_region.cursor_hittest = false;
+        input_region.region = None;
+        assert_eq!(input_region.surface_rects(2.), Some(Vec::new()));
+        input_region.cursor_hittest = true;
+        assert_eq!(input_region.surface_rects(2.), None);
+    }
+
+    #[test]
+    fn partial_input_region() {
+        let region = [
+            (
+                LogicalPosition::new(10., 20.).into(),
+                LogicalSize::new(100., 50.).into(),
+            ),
+            (
+                PhysicalPosition::new(20, 40).into(),
+                PhysicalSize::new(200, 100).into(),
+            ),
+        ];
+
+        assert_eq!(
+            input_region_rects(&region, 2.).collect::<Vec<_>>(),
+            [(10, 20, 100, 50), (10, 20, 100, 50)]
+        );
+    }
+
+    #[test]
+    fn ime_cursor_rectangle_is_surface_local() {
+        let logical = (
+            Position::Logical(LogicalPosition::new(100.0, 50.0)),
+            Size::Logical(LogicalSize::new(10.0, 20.0)),
+        );
+        let physical = (
+            Position::Physical(PhysicalPosition::new(200, 100)),
+            Size::Physical(PhysicalSize::new(20, 40)),
+        );
+
+        assert_eq!(
+            ime_cursor_rectangle(logical.0, logical.1, 2.0),
+            (100, 50, 10, 20)
+        );
+        assert_eq!(
+            ime_cursor_rectangle(physical.0, physical.1, 2.0),
+            (100, 50, 10, 20)
+        );
+        assert_eq!(
+            ime_cursor_rectangle(physical.0, physical.1, 1.0),
+            (200, 100, 20, 40)
+        );
+    }
+}
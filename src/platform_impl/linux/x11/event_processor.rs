@@ -1121,6 +1121,7 @@ impl EventProcessor {
                     _ => unreachable!(),
                 },
                 phase: TouchPhase::Moved,
+                source: None,
             },
             8 => WindowEvent::MouseInput {
                 device_id,
@@ -1216,6 +1217,7 @@ impl EventProcessor {
                     device_id,
                     delta,
                     phase: TouchPhase::Moved,
+                    source: None,
                 }
             } else {
                 WindowEvent::AxisMotion {
@@ -1266,7 +1268,11 @@ impl EventProcessor {
             }
         }
 
-        if self.window_exists(window) {
+        let entered = self.with_window(window, |window| {
+            window.shared_state_lock().cursor_pos = Some((event.event_x, event.event_y));
+        });
+
+        if entered.is_some() {
             let position = PhysicalPosition::new(event.event_x, event.event_y);
 
             let event = Event::WindowEvent {
@@ -1298,7 +1304,11 @@ impl EventProcessor {
 
         // Leave, FocusIn, and FocusOut can be received by a window that's already
         // been destroyed, which the user presumably doesn't want to deal with.
-        if self.window_exists(window) {
+        let left = self.with_window(window, |window| {
+            window.shared_state_lock().cursor_pos = None;
+        });
+
+        if left.is_some() {
             let event = Event::WindowEvent {
                 window_id: mkwid(window),
                 event: WindowEvent::CursorLeft {
@@ -1698,13 +1708,23 @@ impl EventProcessor {
                         xev.locked_group as u32,
                     );
 
+                    let mods = state.modifiers().into();
+                    let layout = self.xkb_context.take_layout_change();
+
                     let window_id = match self.active_window.map(super::mkwid) {
                         Some(window_id) => window_id,
                         None => return,
                     };
 
-                    let mods = state.modifiers().into();
                     self.send_modifiers(window_id, mods, true, &mut callback);
+
+                    if let Some(layout) = layout {
+                        let event = Event::WindowEvent {
+                            window_id,
+                            event: WindowEvent::KeyboardLayoutChanged(layout),
+                        };
+                        callback(&self.target, event);
+                    }
                 }
             }
             _ => {}
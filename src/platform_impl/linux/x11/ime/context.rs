@@ -1,4 +1,5 @@
 use std::ffi::CStr;
+use std::ops::Range;
 use std::os::raw::c_short;
 use std::sync::Arc;
 use std::{mem, ptr};
@@ -73,6 +74,13 @@ fn calc_byte_position(text: &[char], pos: usize) -> usize {
         .fold(0, |byte_pos, text| byte_pos + text.len_utf8())
 }
 
+/// Replace the `range` of the preedit `text` with the `new_text` from the IME.
+///
+/// The IME could send invalid UTF-8, which is replaced with `U+FFFD` instead of failing.
+fn replace_preedit_text(text: &mut Vec<char>, range: Range<usize>, new_text: &[u8]) {
+    text.splice(range, String::from_utf8_lossy(new_text).chars());
+}
+
 /// Preedit text information to be drawn inline by the client.
 extern "C" fn preedit_draw_callback(
     _xim: ffi::XIM,
@@ -96,8 +104,8 @@ extern "C" fn preedit_draw_callback(
     }
 
     // NULL indicate text deletion
-    let mut new_chars = if call_data.text.is_null() {
-        Vec::new()
+    let new_text = if call_data.text.is_null() {
+        &[]
     } else {
         let xim_text = unsafe { &mut *(call_data.text) };
         if xim_text.encoding_is_wchar > 0 {
@@ -110,16 +118,9 @@ extern "C" fn preedit_draw_callback(
             return;
         }
 
-        let new_text = unsafe { CStr::from_ptr(new_text) };
-
-        String::from(new_text.to_str().expect("Invalid UTF-8 String from IME"))
-            .chars()
-            .collect()
+        unsafe { CStr::from_ptr(new_text) }.to_bytes()
     };
-    let mut old_text_tail = client_data.text.split_off(chg_range.end);
-    client_data.text.truncate(chg_range.start);
-    client_data.text.append(&mut new_chars);
-    client_data.text.append(&mut old_text_tail);
+    replace_preedit_text(&mut client_data.text, chg_range, new_text);
     let cursor_byte_pos = calc_byte_position(&client_data.text, client_data.cursor_pos);
 
     client_data
@@ -383,3 +384,19 @@ impl ImeContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_utf8_in_preedit_is_replaced() {
+        let mut text: Vec<char> = "abc".chars().collect();
+
+        replace_preedit_text(&mut text, 1..2, b"x\xF0\x28y");
+        assert_eq!(text.iter().collect::<String>(), "ax\u{FFFD}(yc");
+
+        replace_preedit_text(&mut text, 0..1, &[]);
+        assert_eq!(text.iter().collect::<String>(), "x\u{FFFD}(yc");
+    }
+}
@@ -1,4 +1,4 @@
-use std::{slice, str};
+use std::slice;
 use x11rb::protocol::{
     xinput::{self, ConnectionExt as _},
     xkb,
@@ -106,6 +106,6 @@ impl XConnection {
             unsafe { slice::from_raw_parts(buffer.as_ptr() as *const u8, count as usize) }
         };
 
-        str::from_utf8(bytes).unwrap_or("").to_string()
+        String::from_utf8_lossy(bytes).into_owned()
     }
 }
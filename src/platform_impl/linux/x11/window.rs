@@ -36,8 +36,8 @@ use crate::{
         PlatformIcon, VideoModeHandle as PlatformVideoModeHandle,
     },
     window::{
-        CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, WindowAttributes,
-        WindowButtons, WindowLevel,
+        CursorGrabMode, ImeContentHints, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+        WindowAttributes, WindowButtons, WindowLevel,
     },
 };
 
@@ -74,6 +74,8 @@ pub struct SharedState {
     pub has_focus: bool,
     // Use `Option` to not apply hittest logic when it was never requested.
     pub cursor_hittest: Option<bool>,
+    pub ime_cursor_area: Option<(Position, Size)>,
+    pub ime_allowed: bool,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -115,6 +117,8 @@ impl SharedState {
             base_size: None,
             has_focus: false,
             cursor_hittest: None,
+            ime_cursor_area: None,
+            ime_allowed: false,
         })
     }
 }
@@ -135,6 +139,7 @@ pub struct UnownedWindow {
     #[allow(clippy::mutex_atomic)]
     cursor_visible: Mutex<bool>,
     ime_sender: Mutex<ImeSender>,
+    ime_supported: bool,
     pub shared_state: Mutex<SharedState>,
     redraw_sender: WakeSender<WindowId>,
     activation_sender: WakeSender<super::ActivationToken>,
@@ -370,6 +375,7 @@ impl UnownedWindow {
             cursor_grabbed_mode: Mutex::new(CursorGrabMode::None),
             cursor_visible: Mutex::new(true),
             ime_sender: Mutex::new(event_loop.ime_sender.clone()),
+            ime_supported: event_loop.ime.is_some(),
             shared_state: SharedState::new(guessed_monitor, &window_attrs),
             redraw_sender: event_loop.redraw_sender.clone(),
             activation_sender: event_loop.activation_sender.clone(),
@@ -1126,6 +1132,12 @@ impl UnownedWindow {
         self.shared_state_lock().is_decorated
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        // The window is always `XdndAware`.
+        true
+    }
+
     fn set_maximizable_inner(&self, maximizable: bool) -> Result<VoidCookie<'_>, X11Error> {
         let mut hints = self.xconn.get_motif_hints(self.xwindow);
 
@@ -1707,6 +1719,13 @@ impl UnownedWindow {
         self.set_cursor_position_physical(x, y)
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.shared_state_lock()
+            .cursor_pos
+            .map(|(x, y)| PhysicalPosition::new(x, y))
+    }
+
     #[inline]
     pub fn set_cursor_hittest(&self, hittest: bool) -> Result<(), ExternalError> {
         let mut rectangles: Vec<Rectangle> = Vec::new();
@@ -1804,7 +1823,8 @@ impl UnownedWindow {
     }
 
     #[inline]
-    pub fn set_ime_cursor_area(&self, spot: Position, _size: Size) {
+    pub fn set_ime_cursor_area(&self, spot: Position, size: Size) {
+        self.shared_state_lock().ime_cursor_area = Some((spot, size));
         let (x, y) = spot.to_physical::<i32>(self.scale_factor()).into();
         let _ = self.ime_sender.lock().unwrap().send(ImeRequest::Position(
             self.xwindow as ffi::Window,
@@ -1813,8 +1833,19 @@ impl UnownedWindow {
         ));
     }
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.shared_state_lock().ime_cursor_area
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        self.ime_supported
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, allowed: bool) {
+        self.shared_state_lock().ime_allowed = allowed;
         let _ = self
             .ime_sender
             .lock()
@@ -1825,6 +1856,25 @@ impl UnownedWindow {
     #[inline]
     pub fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {}
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        self.shared_state_lock().ime_allowed
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        ImePurpose::Normal
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, _bypass: bool) {}
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {}
+
     #[inline]
     pub fn focus_window(&self) {
         let atoms = self.xconn.atoms();
@@ -1925,6 +1975,14 @@ impl UnownedWindow {
         // TODO timer
     }
 
+    #[inline]
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    #[inline]
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     #[cfg(feature = "rwh_04")]
     #[inline]
     pub fn raw_window_handle_rwh_04(&self) -> rwh_04::RawWindowHandle {
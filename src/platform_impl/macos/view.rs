@@ -679,6 +679,7 @@ declare_class!(
                 device_id: DEVICE_ID,
                 delta,
                 phase,
+                source: None,
             });
         }
 
@@ -858,6 +859,10 @@ impl WinitView {
         }
     }
 
+    pub(super) fn ime_allowed(&self) -> bool {
+        self.ivars().ime_allowed.get()
+    }
+
     pub(super) fn set_ime_allowed(&self, ime_allowed: bool) {
         if self.ivars().ime_allowed.get() == ime_allowed {
             return;
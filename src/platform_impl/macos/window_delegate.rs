@@ -39,8 +39,8 @@ use crate::error::{ExternalError, NotSupportedError, OsError as RootOsError};
 use crate::event::WindowEvent;
 use crate::platform::macos::{OptionAsAlt, WindowExtMacOS};
 use crate::window::{
-    Cursor, CursorGrabMode, Icon, ImePurpose, ResizeDirection, Theme, UserAttentionType,
-    WindowAttributes, WindowButtons, WindowLevel,
+    Cursor, CursorGrabMode, Icon, ImeContentHints, ImePurpose, ResizeDirection, Theme,
+    UserAttentionType, WindowAttributes, WindowButtons, WindowLevel,
 };
 
 #[derive(Clone, Debug)]
@@ -120,6 +120,8 @@ pub(crate) struct State {
     standard_frame: Cell<Option<NSRect>>,
     is_simple_fullscreen: Cell<bool>,
     saved_style: Cell<Option<NSWindowStyleMask>>,
+    /// The IME cursor area, as last given to `set_ime_cursor_area`.
+    ime_cursor_area: Cell<Option<(Position, Size)>>,
 }
 
 declare_class!(
@@ -681,6 +683,7 @@ impl WindowDelegate {
             standard_frame: Cell::new(None),
             is_simple_fullscreen: Cell::new(false),
             saved_style: Cell::new(None),
+            ime_cursor_area: Cell::new(None),
         });
         let delegate: Id<WindowDelegate> = unsafe { msg_send_id![super(delegate), init] };
 
@@ -848,6 +851,14 @@ impl WindowDelegate {
     #[inline]
     pub fn pre_present_notify(&self) {}
 
+    #[inline]
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    #[inline]
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
         let position = flip_window_screen_coordinates(self.window().frame());
         Ok(LogicalPosition::new(position.x, position.y).to_physical(self.scale_factor()))
@@ -1102,6 +1113,11 @@ impl WindowDelegate {
         Ok(())
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
     #[inline]
     pub fn drag_window(&self) -> Result<(), ExternalError> {
         let mtm = MainThreadMarker::from(self);
@@ -1475,6 +1491,11 @@ impl WindowDelegate {
         self.ivars().decorations.get()
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        true
+    }
+
     #[inline]
     pub fn set_window_level(&self, level: WindowLevel) {
         let level = match level {
@@ -1499,6 +1520,8 @@ impl WindowDelegate {
 
     #[inline]
     pub fn set_ime_cursor_area(&self, spot: Position, size: Size) {
+        self.ivars().ime_cursor_area.set(Some((spot, size)));
+
         let scale_factor = self.scale_factor();
         let logical_spot = spot.to_logical(scale_factor);
         let logical_spot = NSPoint::new(logical_spot.x, logical_spot.y);
@@ -1509,6 +1532,16 @@ impl WindowDelegate {
         self.view().set_ime_cursor_area(logical_spot, size);
     }
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.ivars().ime_cursor_area.get()
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        true
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, allowed: bool) {
         self.view().set_ime_allowed(allowed);
@@ -1517,6 +1550,25 @@ impl WindowDelegate {
     #[inline]
     pub fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {}
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        self.view().ime_allowed()
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        ImePurpose::Normal
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, _bypass: bool) {}
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {}
+
     #[inline]
     pub fn focus_window(&self) {
         let mtm = MainThreadMarker::from(self);
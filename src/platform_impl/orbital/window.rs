@@ -9,7 +9,7 @@ use crate::{
     error,
     platform_impl::Fullscreen,
     window,
-    window::ImePurpose,
+    window::{ImeContentHints, ImePurpose},
 };
 
 use super::{
@@ -199,6 +199,14 @@ impl Window {
     #[inline]
     pub fn pre_present_notify(&self) {}
 
+    #[inline]
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    #[inline]
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     #[inline]
     pub fn reset_dead_keys(&self) {
         // TODO?
@@ -352,6 +360,11 @@ impl Window {
         !self.get_flag(ORBITAL_FLAG_BORDERLESS).unwrap_or(false)
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_window_level(&self, level: window::WindowLevel) {
         match level {
@@ -374,12 +387,41 @@ impl Window {
     #[inline]
     pub fn set_ime_cursor_area(&self, _position: Position, _size: Size) {}
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        None
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, _allowed: bool) {}
 
     #[inline]
     pub fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {}
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        ImePurpose::Normal
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, _bypass: bool) {}
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {}
+
     #[inline]
     pub fn focus_window(&self) {}
 
@@ -396,6 +438,11 @@ impl Window {
         ))
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
     #[inline]
     pub fn set_cursor_grab(
         &self,
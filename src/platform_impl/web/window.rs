@@ -2,7 +2,7 @@ use crate::dpi::{PhysicalPosition, PhysicalSize, Position, Size};
 use crate::error::{ExternalError, NotSupportedError, OsError as RootOE};
 use crate::icon::Icon;
 use crate::window::{
-    Cursor, CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Cursor, CursorGrabMode, ImeContentHints, ImePurpose, ResizeDirection, Theme, UserAttentionType,
     WindowAttributes, WindowButtons, WindowId as RootWI, WindowLevel,
 };
 
@@ -145,6 +145,12 @@ impl Inner {
 
     pub fn pre_present_notify(&self) {}
 
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
         Ok(self
             .canvas
@@ -240,6 +246,11 @@ impl Inner {
         Err(ExternalError::NotSupported(NotSupportedError::new()))
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        None
+    }
+
     #[inline]
     pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
         let lock = match mode {
@@ -330,6 +341,11 @@ impl Inner {
         true
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_window_level(&self, _level: WindowLevel) {
         // Intentionally a no-op, no window ordering
@@ -345,6 +361,16 @@ impl Inner {
         // Currently a no-op as it does not seem there is good support for this on web
     }
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        None
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        false
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, _allowed: bool) {
         // Currently not implemented
@@ -355,6 +381,33 @@ impl Inner {
         // Currently not implemented
     }
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {
+        // Currently not implemented
+    }
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        // Currently not implemented
+        false
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        // Currently not implemented
+        ImePurpose::Normal
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, _bypass: bool) {
+        // Currently not implemented
+    }
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {
+        // Currently not implemented
+    }
+
     #[inline]
     pub fn focus_window(&self) {
         let _ = self.canvas.borrow().raw().focus();
@@ -84,8 +84,8 @@ use crate::{
         Fullscreen, SelectedCursor, WindowId,
     },
     window::{
-        CursorGrabMode, ImePurpose, ResizeDirection, Theme, UserAttentionType, WindowAttributes,
-        WindowButtons, WindowLevel,
+        CursorGrabMode, ImeContentHints, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+        WindowAttributes, WindowButtons, WindowLevel,
     },
 };
 
@@ -176,6 +176,14 @@ impl Window {
     #[inline]
     pub fn pre_present_notify(&self) {}
 
+    #[inline]
+    pub fn set_max_frame_rate(&self, _rate: Option<u32>) {}
+
+    #[inline]
+    pub fn frame_stats(&self) -> crate::window::FrameStats {
+        Default::default()
+    }
+
     #[inline]
     pub fn outer_position(&self) -> Result<PhysicalPosition<i32>, NotSupportedError> {
         util::WindowArea::Outer.get_rect(self.hwnd())
@@ -496,6 +504,20 @@ impl Window {
         Ok(())
     }
 
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        let window_state = self.window_state_lock();
+        if window_state
+            .mouse
+            .cursor_flags()
+            .contains(CursorFlags::IN_WINDOW)
+        {
+            window_state.mouse.last_position
+        } else {
+            None
+        }
+    }
+
     unsafe fn handle_os_dragging(&self, wparam: WPARAM) {
         let window = self.window;
         let window_state = self.window_state.clone();
@@ -874,6 +896,11 @@ impl Window {
             .contains(WindowFlags::MARKER_DECORATIONS)
     }
 
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        self.window_state_lock().drag_and_drop
+    }
+
     #[inline]
     pub fn set_window_level(&self, level: WindowLevel) {
         let window = self.window;
@@ -930,6 +957,8 @@ impl Window {
 
     #[inline]
     pub fn set_ime_cursor_area(&self, spot: Position, size: Size) {
+        self.window_state_lock().ime_cursor_area = Some((spot, size));
+
         let window = self.window;
         let state = self.window_state.clone();
         self.thread_executor.execute_in_thread(move || unsafe {
@@ -938,6 +967,16 @@ impl Window {
         });
     }
 
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.window_state_lock().ime_cursor_area
+    }
+
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        true
+    }
+
     #[inline]
     pub fn set_ime_allowed(&self, allowed: bool) {
         let window = self.window;
@@ -951,6 +990,25 @@ impl Window {
     #[inline]
     pub fn set_ime_purpose(&self, _purpose: ImePurpose) {}
 
+    #[inline]
+    pub fn set_ime_content_hints(&self, _hints: ImeContentHints) {}
+
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        self.window_state_lock().ime_allowed
+    }
+
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        ImePurpose::Normal
+    }
+
+    #[inline]
+    pub fn set_ime_bypass(&self, _bypass: bool) {}
+
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, _text: String, _cursor: usize, _anchor: usize) {}
+
     #[inline]
     pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
         let window = self.window;
@@ -1,5 +1,5 @@
 use crate::{
-    dpi::{PhysicalPosition, PhysicalSize, Size},
+    dpi::{PhysicalPosition, PhysicalSize, Position, Size},
     icon::Icon,
     keyboard::ModifiersState,
     platform_impl::platform::{event_loop, util, Fullscreen, SelectedCursor},
@@ -48,6 +48,9 @@ pub(crate) struct WindowState {
 
     pub ime_state: ImeState,
     pub ime_allowed: bool,
+    pub ime_cursor_area: Option<(Position, Size)>,
+
+    pub drag_and_drop: bool,
 
     // Used by WM_NCACTIVATE, WM_SETFOCUS and WM_KILLFOCUS
     pub is_active: bool,
@@ -169,6 +172,9 @@ impl WindowState {
 
             ime_state: ImeState::Disabled,
             ime_allowed: false,
+            ime_cursor_area: None,
+
+            drag_and_drop: attributes.platform_specific.drag_and_drop,
 
             is_active: false,
             is_focused: false,
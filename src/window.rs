@@ -1,5 +1,6 @@
 //! The [`Window`] struct and associated types.
 use std::fmt;
+use std::time::Duration;
 
 use crate::{
     dpi::{PhysicalPosition, PhysicalSize, Position, Size},
@@ -635,7 +636,7 @@ impl Window {
     /// ## Example
     ///
     /// This example illustrates how it looks with OpenGL, but it applies to other graphics
-    /// APIs and software rendering.
+    /// APIs and software rendering. With Vulkan, call it right before `vkQueuePresentKHR`.
     ///
     /// ```no_run
     /// # use winit::window::Window;
@@ -654,6 +655,8 @@ impl Window {
     /// ## Platform-specific
     ///
     /// **Wayland:** - schedules a frame callback to throttle [`WindowEvent::RedrawRequested`].
+    /// The callback is only armed by the commit the present does, so calling this after the
+    /// present delays it by a frame.
     ///
     /// [`WindowEvent::RedrawRequested`]: crate::event::WindowEvent::RedrawRequested
     #[inline]
@@ -661,6 +664,44 @@ impl Window {
         self.window.maybe_queue_on_main(|w| w.pre_present_notify());
     }
 
+    /// Limit the rate of [`WindowEvent::RedrawRequested`] to `rate` frames per second.
+    ///
+    /// The redraws requested sooner are deferred until the limit allows them. This is useful to
+    /// save power in the background or on battery. `None` or `0` removes the limit, which is the
+    /// default.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The limit applies on top of the frame callback throttling, see
+    ///   [`Window::pre_present_notify`].
+    /// - **Android / iOS / macOS / Orbital / Web / Windows / X11:** Unsupported.
+    ///
+    /// [`WindowEvent::RedrawRequested`]: crate::event::WindowEvent::RedrawRequested
+    #[inline]
+    pub fn set_max_frame_rate(&self, rate: Option<u32>) {
+        self.window
+            .maybe_queue_on_main(move |w| w.set_max_frame_rate(rate))
+    }
+
+    /// The timings of the frames presented to the window, for diagnosing the frame pacing.
+    ///
+    /// The statistics are updated on every present, so [`Window::pre_present_notify`] must be
+    /// called for them to be collected. A frame is missed when it's shown one or more refresh
+    /// intervals later than expected. This is only counted while presenting right after the
+    /// previous frame was shown, so not having anything to draw isn't missing frames.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Estimated from the frame callbacks, which are only precise to a
+    ///   millisecond. The refresh interval is the one the compositor paces the window at, which
+    ///   could be longer than the one of the output when the compositor throttles the window.
+    /// - **Android / iOS / macOS / Orbital / Web / Windows / X11:** Unsupported, the statistics
+    ///   are always empty.
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.window.maybe_wait_on_main(|w| w.frame_stats())
+    }
+
     /// Reset the dead key state of the keyboard.
     ///
     /// This is useful when a dead key is bound to trigger an action. Then
@@ -801,6 +842,10 @@ impl Window {
     /// ## Platform-specific
     ///
     /// - **Web:** Sets the size of the canvas element. Doesn't account for CSS [`transform`].
+    /// - **Wayland:** The size is always returned right away, since the client picks the size of
+    ///   a floating window itself; the compositor may still send a different size later. While
+    ///   the window is maximized, fullscreen, or tiled, the compositor decides the size, so the
+    ///   request is ignored and the current size is returned.
     ///
     /// [`WindowEvent::Resized`]: crate::event::WindowEvent::Resized
     /// [`transform`]: https://developer.mozilla.org/en-US/docs/Web/CSS/transform
@@ -969,7 +1014,10 @@ impl Window {
     /// ## Platform-specific
     ///
     /// - **X11:** Not implemented.
-    /// - **Wayland / iOS / Android / Web:** Unsupported.
+    /// - **Wayland:** Returns `Some(false)` while the compositor suspends the window, for
+    ///   example when it's minimized or fully hidden, or holds back its frame callbacks for a
+    ///   second. `None` until the window is configured.
+    /// - **iOS / Android / Web:** Unsupported.
     #[inline]
     pub fn is_visible(&self) -> Option<bool> {
         self.window.maybe_wait_on_main(|w| w.is_visible())
@@ -1049,7 +1097,11 @@ impl Window {
     ///
     /// ## Platform-specific
     ///
-    /// - **Wayland**: always `None`.
+    /// - **Wayland**: Best-effort, since the compositor doesn't tell. `Some(false)` is returned
+    ///   while the window is activated, and `Some(true)` when it was minimized with
+    ///   [`Window::set_minimized`] or the client-side decorations and since then either suspended
+    ///   by the compositor or not receiving frame callbacks for a second after
+    ///   [`Window::request_redraw`]. `None` is returned otherwise.
     /// - **iOS / Android / Web / Orbital:** Unsupported.
     #[inline]
     pub fn is_minimized(&self) -> Option<bool> {
@@ -1148,6 +1200,25 @@ impl Window {
         self.window.maybe_wait_on_main(|w| w.is_decorated())
     }
 
+    /// Whether the window can receive files dragged onto it, as [`WindowEvent::HoveredFile`]
+    /// and [`WindowEvent::DroppedFile`].
+    ///
+    /// This can be used to hide the drop targets when file drops will never arrive.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** Returns `false` when disabled with
+    ///   `WindowAttributesExtWindows::with_drag_and_drop`.
+    /// - **Wayland / iOS / Android / Web / Orbital:** Unsupported, always returns `false`.
+    ///
+    /// [`WindowEvent::HoveredFile`]: crate::event::WindowEvent::HoveredFile
+    /// [`WindowEvent::DroppedFile`]: crate::event::WindowEvent::DroppedFile
+    #[inline]
+    pub fn drag_and_drop_supported(&self) -> bool {
+        self.window
+            .maybe_wait_on_main(|w| w.drag_and_drop_supported())
+    }
+
     /// Change the window level.
     ///
     /// This is just a hint to the OS, and the system could ignore it.
@@ -1220,11 +1291,42 @@ impl Window {
             .maybe_queue_on_main(move |w| w.set_ime_cursor_area(position, size))
     }
 
+    /// Gets the IME cursor editing area last set with [`Window::set_ime_cursor_area`].
+    ///
+    /// The area is returned exactly as it was passed, so its units are the ones used by the
+    /// caller. `None` means that the area was never set.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Orbital:** Unsupported, always returns `None`.
+    #[inline]
+    pub fn ime_cursor_area(&self) -> Option<(Position, Size)> {
+        self.window.maybe_wait_on_main(|w| w.ime_cursor_area())
+    }
+
+    /// Returns whether IME could be allowed on the window with [`Window::set_ime_allowed`].
+    ///
+    /// When it's not supported, allowing IME is a no-op and no [`Ime`] events are sent, so the
+    /// IME related UI could be hidden.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** `false` when no input method could be opened.
+    /// - **Wayland:** `false` when the compositor doesn't support text input.
+    /// - **iOS / Android / Web / Orbital:** Always `false`.
+    ///
+    /// [`Ime`]: crate::event::WindowEvent::Ime
+    #[inline]
+    pub fn is_ime_supported(&self) -> bool {
+        self.window.maybe_wait_on_main(|w| w.is_ime_supported())
+    }
+
     /// Sets whether the window should get IME events
     ///
     /// When IME is allowed, the window will receive [`Ime`] events, and during the
-    /// preedit phase the window will NOT get [`KeyboardInput`] events. The window
-    /// should allow IME while it is expecting text input.
+    /// preedit phase the window will NOT get [`KeyboardInput`] events for the keys composing
+    /// the text. The keys the IME doesn't take, like the shortcuts, still come as
+    /// [`KeyboardInput`]. The window should allow IME while it is expecting text input.
     ///
     /// When IME is not allowed, the window won't receive [`Ime`] events, and will
     /// receive [`KeyboardInput`] events for every keypress instead. Not allowing
@@ -1237,6 +1339,10 @@ impl Window {
     /// - **macOS:** IME must be enabled to receive text-input where dead-key sequences are combined.
     /// - **iOS / Android / Web / Orbital:** Unsupported.
     /// - **X11**: Enabling IME will disable dead keys reporting during compose.
+    /// - **Wayland:** Allowing IME is ignored when the compositor doesn't support text input,
+    ///   which is reported by [`Window::is_ime_supported`]. Which keys
+    ///   are taken is up to the input method, so the key presses could come while composing;
+    ///   `WindowExtWayland::set_ime_key_presses_while_composing` holds them back.
     ///
     /// [`Ime`]: crate::event::WindowEvent::Ime
     /// [`KeyboardInput`]: crate::event::WindowEvent::KeyboardInput
@@ -1246,6 +1352,17 @@ impl Window {
             .maybe_queue_on_main(move |w| w.set_ime_allowed(allowed))
     }
 
+    /// Returns whether IME is allowed, as last set with [`Window::set_ime_allowed`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Orbital:** Unsupported, always returns `false`.
+    /// - **Wayland:** Stays `false` when the compositor doesn't support text input.
+    #[inline]
+    pub fn ime_allowed(&self) -> bool {
+        self.window.maybe_wait_on_main(|w| w.ime_allowed())
+    }
+
     /// Sets the IME purpose for the window using [`ImePurpose`].
     ///
     /// ## Platform-specific
@@ -1257,6 +1374,75 @@ impl Window {
             .maybe_queue_on_main(move |w| w.set_ime_purpose(purpose))
     }
 
+    /// Sets the hints for the IME about the expected text, refining the purpose set with
+    /// [`Window::set_ime_purpose`].
+    ///
+    /// No hints are set by default, leaving the IME behavior to the purpose. The hints apply to
+    /// every text input focusing the window, until they're set again.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    #[inline]
+    pub fn set_ime_content_hints(&self, hints: ImeContentHints) {
+        self.window
+            .maybe_queue_on_main(move |w| w.set_ime_content_hints(hints))
+    }
+
+    /// Returns the IME purpose last set with [`Window::set_ime_purpose`], which is
+    /// [`ImePurpose::Normal`] by default.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported, always returns
+    ///   [`ImePurpose::Normal`].
+    #[inline]
+    pub fn ime_purpose(&self) -> ImePurpose {
+        self.window.maybe_wait_on_main(|w| w.ime_purpose())
+    }
+
+    /// Bypasses the IME for a moment, like while a keyboard shortcut is held, so its keys aren't
+    /// taken by the IME.
+    ///
+    /// Unlike [`Window::set_ime_allowed`], no [`Ime::Disabled`] is sent, the IME is only set aside
+    /// until the bypass ends. A preedit in progress is discarded when the bypass starts, with an
+    /// empty [`Ime::Preedit`], and never committed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    ///
+    /// [`Ime::Disabled`]: crate::event::Ime::Disabled
+    /// [`Ime::Preedit`]: crate::event::Ime::Preedit
+    #[inline]
+    pub fn set_ime_bypass(&self, bypass: bool) {
+        self.window
+            .maybe_queue_on_main(move |w| w.set_ime_bypass(bypass))
+    }
+
+    /// Sets the text around the IME cursor, so the input method can use it for suggestions and
+    /// for [`Ime::DeleteSurroundingText`].
+    ///
+    /// The `cursor` and the `anchor` of the selection are byte offsets into `text`, and must be on
+    /// character boundaries. They are the same when nothing is selected. The text should be set
+    /// again whenever it or the cursor changes, including after handling the [`Ime`] events.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    /// - **Wayland:** The first text set after an [`Ime::Commit`] or an
+    ///   [`Ime::DeleteSurroundingText`] is reported to the input method as its own change, the
+    ///   others as changed by the application.
+    ///
+    /// [`Ime`]: crate::event::Ime
+    /// [`Ime::Commit`]: crate::event::Ime::Commit
+    /// [`Ime::DeleteSurroundingText`]: crate::event::Ime::DeleteSurroundingText
+    #[inline]
+    pub fn set_ime_surrounding_text(&self, text: String, cursor: usize, anchor: usize) {
+        self.window
+            .maybe_queue_on_main(move |w| w.set_ime_surrounding_text(text, cursor, anchor))
+    }
+
     /// Brings the window to the front and sets input focus. Has no effect if the window is
     /// already in focus, minimized, or not visible.
     ///
@@ -1320,7 +1506,13 @@ impl Window {
     /// ## Platform-specific
     ///
     /// - **macOS:** This is an app-wide setting.
-    /// - **iOS / Android / Wayland / x11 / Orbital:** Unsupported.
+    /// - **Wayland:** Returns the theme set with [`Window::set_theme`] or the `color-scheme` of
+    ///   the XDG settings portal with the `wayland-portal` feature. `None` when neither is
+    ///   available. The portal is read asynchronously once the first window is created, so the
+    ///   system theme only shows up with the [`WindowEvent::ThemeChanged`] which follows.
+    /// - **iOS / Android / x11 / Orbital:** Unsupported.
+    ///
+    /// [`WindowEvent::ThemeChanged`]: crate::event::WindowEvent::ThemeChanged
     #[inline]
     pub fn theme(&self) -> Option<Theme> {
         self.window.maybe_wait_on_main(|w| w.theme())
@@ -1355,6 +1547,9 @@ impl Window {
 impl Window {
     /// Modifies the cursor icon of the window.
     ///
+    /// The cursor belongs to the window, so every window can have its own, which is shown
+    /// whenever the pointer enters it.
+    ///
     /// ## Platform-specific
     ///
     /// - **iOS / Android / Orbital:** Unsupported.
@@ -1399,6 +1594,22 @@ impl Window {
             .maybe_wait_on_main(|w| w.set_cursor_position(position))
     }
 
+    /// Returns the last known position of the cursor in window coordinates.
+    ///
+    /// Returns `None` when the cursor isn't over the window. This is the same position as the
+    /// last [`WindowEvent::CursorMoved`], which makes it handy for hit-testing client-side
+    /// decorations without tracking the events.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS / iOS / Android / Web / Orbital:** Unsupported, always returns `None`.
+    ///
+    /// [`WindowEvent::CursorMoved`]: crate::event::WindowEvent::CursorMoved
+    #[inline]
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.window.maybe_wait_on_main(|w| w.cursor_position())
+    }
+
     /// Set grabbing [mode](CursorGrabMode) on the cursor preventing it from leaving the window.
     ///
     /// # Example
@@ -1758,6 +1969,14 @@ pub enum ImePurpose {
     ///
     /// For example, that could alter OSK on Wayland to show extra buttons.
     Terminal,
+    /// The IME is used to input a URL, like in the address bar of a browser.
+    Url,
+    /// The IME is used to input an email address.
+    Email,
+    /// The IME is used to input a number, which could have a sign and a decimal separator.
+    Number,
+    /// The IME is used to input only digits, like a PIN.
+    Digits,
 }
 
 impl Default for ImePurpose {
@@ -1766,6 +1985,48 @@ impl Default for ImePurpose {
     }
 }
 
+bitflags::bitflags! {
+    /// Hints for the IME about the expected text, for use in [`Window::set_ime_content_hints`].
+    ///
+    /// The hints refine the [`ImePurpose`], so the IME could for example skip the
+    /// autocorrection of code. The IME is free to ignore them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / X11 / macOS / Orbital:** Unsupported.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ImeContentHints: u32 {
+        /// Suggest completions of the text.
+        const COMPLETION = 1 << 0;
+        /// Check the spelling, which includes the autocorrection.
+        const SPELLCHECK = 1 << 1;
+        /// Capitalize the first letter of the sentences.
+        const AUTO_CAPITALIZATION = 1 << 2;
+        /// Prefer lowercase letters.
+        const LOWERCASE = 1 << 3;
+        /// Prefer uppercase letters.
+        const UPPERCASE = 1 << 4;
+        /// Prefer capitalizing the first letter of every word.
+        const TITLECASE = 1 << 5;
+        /// Only Latin characters are expected.
+        const LATIN = 1 << 6;
+        /// The text could span several lines.
+        const MULTILINE = 1 << 7;
+    }
+}
+
+/// The timings of the frames presented to a window, see [`Window::frame_stats`].
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameStats {
+    /// The time since [`Window::pre_present_notify`] was last called.
+    pub since_last_present: Option<Duration>,
+    /// The estimated interval between two frames shown by the display, once known.
+    pub refresh_interval: Option<Duration>,
+    /// The number of frames missed while presenting continuously, since the window was created.
+    pub missed_frames: u64,
+}
+
 /// An opaque token used to activate the [`Window`].
 ///
 /// [`Window`]: crate::window::Window